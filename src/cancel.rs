@@ -0,0 +1,279 @@
+use crate::{ChunkEvent, ChunkList};
+use rayon::prelude::*;
+use std::fmt::{self, Debug, Display};
+use std::mem;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Shared flag that cancels the operations it's passed to, e.g. from a UI thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every operation using this token (or a clone of it) to stop.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether `cancel` has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// How far an operation has got, in chunks processed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// The operation was cancelled and the list was left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "operation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Checks the token before every chunk and reports each processed chunk.
+struct Tracker<'a, F> {
+    token: &'a CancellationToken,
+    progress: &'a F,
+    done: AtomicUsize,
+    total: usize,
+}
+
+impl<'a, F: Fn(Progress) + Sync> Tracker<'a, F> {
+    fn new(token: &'a CancellationToken, progress: &'a F, total: usize) -> Self {
+        Self {
+            token,
+            progress,
+            done: AtomicUsize::new(0),
+            total,
+        }
+    }
+
+    fn check(&self) -> Result<(), Cancelled> {
+        match self.token.is_cancelled() {
+            true => Err(Cancelled),
+            false => Ok(()),
+        }
+    }
+
+    /// Count `chunks` more chunks as done.
+    fn advance(&self, chunks: usize) {
+        let done = self.done.fetch_add(chunks, Ordering::Relaxed) + chunks;
+        (self.progress)(Progress { done, total: self.total });
+    }
+}
+
+/// Merge two sorted runs, keeping equal elements in order (like a stable sort).
+fn merge<T: Ord>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let (mut a, mut b) = (a.into_iter().peekable(), b.into_iter().peekable());
+    while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+        if y < x {
+            merged.extend(b.next());
+        } else {
+            merged.extend(a.next());
+        }
+    }
+    merged.extend(a);
+    merged.extend(b);
+    merged
+}
+
+/// Cancellable variants of the long-running operations.
+///
+/// Each takes a `CancellationToken`, checked before every chunk, and a progress callback,
+/// called after every chunk (from whichever thread processed it, so calls may overlap and
+/// arrive out of order). The work is done on a copy of the affected chunks that is only
+/// swapped in once everything finished: on `Err(Cancelled)` the list is unchanged.
+impl<T> ChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Sort the entire list: every chunk is sorted on its own in parallel, then neighbouring
+    /// runs are merged pairwise. Progress counts each chunk once per pass.
+    pub fn sort_cancellable<F>(&mut self, token: &CancellationToken, progress: F) -> Result<(), Cancelled>
+    where F: Fn(Progress) + Sync, {
+        let chunks = self.my_list.len();
+        let merge_passes = chunks.next_power_of_two().trailing_zeros() as usize;
+        let tracker = Tracker::new(token, &progress, chunks * (1 + merge_passes));
+        let work = self.len();
+
+        // Runs carry the number of chunks they cover, for progress
+        let mut runs: Vec<(Vec<T>, usize)> = self.execution.run(work, |min_len| {
+            self.my_list
+                .par_iter()
+                .with_min_len(min_len)
+                .map(|chunk| {
+                    tracker.check()?;
+                    let mut run = chunk.to_vec();
+                    run.sort();
+                    tracker.advance(1);
+                    Ok((run, 1))
+                })
+                .collect::<Result<_, Cancelled>>()
+        })?;
+        while runs.len() > 1 {
+            let mut pairs = Vec::with_capacity(runs.len().div_ceil(2));
+            let mut rest = runs.into_iter();
+            while let Some(a) = rest.next() {
+                pairs.push((a, rest.next()));
+            }
+            runs = self.execution.run(work, |min_len| {
+                pairs
+                    .into_par_iter()
+                    .with_min_len(min_len)
+                    .map(|(a, b)| {
+                        tracker.check()?;
+                        let run = match b {
+                            Some(b) => (merge(a.0, b.0), a.1 + b.1),
+                            None => a,
+                        };
+                        tracker.advance(run.1);
+                        Ok(run)
+                    })
+                    .collect::<Result<_, Cancelled>>()
+            })?;
+        }
+
+        self.refill(runs.pop().map(|(run, _)| run).unwrap_or_default());
+        self.notify(ChunkEvent::Sorted);
+        Ok(())
+    }
+
+    /// Set a new chunk size and rebalance the elements (see `set_chunk_size`).
+    pub fn set_chunk_size_cancellable<F>(&mut self, new_chunk_size: usize, token: &CancellationToken, progress: F) -> Result<(), Cancelled>
+    where F: Fn(Progress) + Sync, {
+        let tracker = Tracker::new(token, &progress, self.my_list.len());
+        tracker.check()?;
+        if new_chunk_size > self.chunk_size {
+            // Nothing to move
+            self.set_chunk_size(new_chunk_size);
+            tracker.advance(tracker.total);
+            return Ok(());
+        }
+
+        let parts: Vec<Vec<T>> = self.execution.run(self.len(), |min_len| {
+            self.my_list
+                .par_iter()
+                .with_min_len(min_len)
+                .map(|chunk| {
+                    tracker.check()?;
+                    let part = chunk.to_vec();
+                    tracker.advance(1);
+                    Ok(part)
+                })
+                .collect::<Result<_, Cancelled>>()
+        })?;
+        let old_chunk_size = mem::replace(&mut self.chunk_size, new_chunk_size);
+        self.refill(parts.into_iter().flatten().collect());
+        self.notify(ChunkEvent::Rebalanced { old_chunk_size, new_chunk_size });
+        Ok(())
+    }
+
+    /// Remove all instances of `t`, in parallel (see `remove_all`).
+    pub fn remove_all_cancellable<F>(&mut self, t: &T, token: &CancellationToken, progress: F) -> Result<(), Cancelled>
+    where F: Fn(Progress) + Sync, {
+        let tracker = Tracker::new(token, &progress, self.my_list.len());
+        let report = self.is_observed();
+        // Sharing the chunks means only those that actually change get copied
+        let mut chunks = self.my_list.clone();
+        let removed: Vec<Vec<(usize, T)>> = self.execution.run(self.len(), |min_len| {
+            chunks
+                .par_iter_mut()
+                .with_min_len(min_len)
+                .map(|chunk| {
+                    tracker.check()?;
+                    let removed = Self::remove_from_chunk(chunk, t, report);
+                    tracker.advance(1);
+                    Ok(removed)
+                })
+                .collect::<Result<_, Cancelled>>()
+        })?;
+        self.my_list = chunks;
+        self.report_removed(removed);
+        Ok(())
+    }
+
+    /// Build a new list by applying `f` to every element (see `map`).
+    pub fn par_map_cancellable<U, M, F>(&self, f: M, token: &CancellationToken, progress: F) -> Result<ChunkList<U>, Cancelled>
+    where U: Ord + Debug + Send + Sync + Clone, M: Fn(&T) -> U + Sync + Send, F: Fn(Progress) + Sync, {
+        let tracker = Tracker::new(token, &progress, self.my_list.len());
+        let mut list = ChunkList::new(self.chunk_size);
+        list.my_list = self.execution.run(self.len(), |min_len| {
+            self.my_list
+                .par_iter()
+                .with_min_len(min_len)
+                .map(|chunk| {
+                    tracker.check()?;
+                    let mapped = Arc::new(chunk.iter().map(&f).collect());
+                    tracker.advance(1);
+                    Ok(mapped)
+                })
+                .collect::<Result<_, Cancelled>>()
+        })?;
+        Ok(list)
+    }
+
+    /// Build a new list of the elements matching `pred` (see `filter`).
+    pub fn par_filter_cancellable<P, F>(&self, pred: P, token: &CancellationToken, progress: F) -> Result<ChunkList<T>, Cancelled>
+    where P: Fn(&T) -> bool + Sync + Send, F: Fn(Progress) + Sync, {
+        let tracker = Tracker::new(token, &progress, self.my_list.len());
+        let kept: Vec<Option<Arc<Vec<T>>>> = self.execution.run(self.len(), |min_len| {
+            self.my_list
+                .par_iter()
+                .with_min_len(min_len)
+                .map(|chunk| {
+                    tracker.check()?;
+                    let kept: Vec<T> = chunk.iter().filter(|item| pred(item)).cloned().collect();
+                    tracker.advance(1);
+                    Ok(match kept.len() {
+                        0 => None,
+                        n if n == chunk.len() => Some(chunk.clone()),
+                        _ => Some(Arc::new(kept)),
+                    })
+                })
+                .collect::<Result<_, Cancelled>>()
+        })?;
+        let mut list = ChunkList::new(self.chunk_size);
+        list.my_list = kept.into_iter().flatten().collect();
+        Ok(list)
+    }
+
+    /// Run `f` on every chunk in parallel (see `par_for_each_chunk`). Every chunk is
+    /// copied first, so a cancelled run leaves no partial changes behind.
+    pub fn par_for_each_chunk_cancellable<K, F>(&mut self, f: K, token: &CancellationToken, progress: F) -> Result<(), Cancelled>
+    where K: Fn(usize, &mut [T]) + Sync + Send, F: Fn(Progress) + Sync, {
+        let tracker = Tracker::new(token, &progress, self.my_list.len());
+        let mut chunks = self.my_list.clone();
+        self.execution.run(self.len(), |min_len| {
+            chunks
+                .par_iter_mut()
+                .enumerate()
+                .with_min_len(min_len)
+                .try_for_each(|(chunk_index, chunk)| {
+                    tracker.check()?;
+                    f(chunk_index, Arc::make_mut(chunk).as_mut_slice());
+                    tracker.advance(1);
+                    Ok(())
+                })
+        })?;
+        let old = mem::replace(&mut self.my_list, chunks);
+        if self.is_observed() {
+            self.report_updates(old);
+        }
+        Ok(())
+    }
+}
@@ -1,275 +1,617 @@
-#![allow(dead_code)]
-use rayon::prelude::*;
-use std::fmt::Debug;
-use std::sync::atomic::{AtomicBool, Ordering};
-
-#[derive(Debug)]
-pub struct ChunkList<T> {
-    my_list: Vec<Vec<T>>,
-    chunk_size: usize,
-}
-
-impl<T> Default for ChunkList<T>
-where T: Ord + Debug + Send + Sync + Clone, {
-    /// Default constructor with chunk size = 1000
-    fn default() -> Self {
-        Self::new(1000)
-    }
-}
-
-impl<T> ChunkList<T>
-where T: Ord + Debug +Send + Sync + Clone, {
-    /// Creates a new ChunkList with the specified chunk size.
-    pub fn new(chunk_size: usize) -> Self {
-        Self {
-            my_list: Vec::new(),
-            chunk_size,
-        }
-    }
-
-    /// Helper: Convert a global index to (chunk_index, position_in_chunk).
-    fn index_to_chunk_pos(&self, index: usize) -> (usize, usize) {
-        let chunk_index = index / self.chunk_size;
-        let pos = index % self.chunk_size;
-        (chunk_index, pos)
-    }
-
-    /// Add an element to the list, finding a chunk that isn't full or creating a new one.
-    pub fn add(&mut self, t: T) {
-        for chunk in &mut self.my_list {
-            if chunk.len() < self.chunk_size {
-                chunk.push(t);
-                return;
-            }
-        }
-        // If we get here, all chunks are full -> create a new chunk
-        let mut new_chunk = Vec::with_capacity(self.chunk_size);
-        new_chunk.push(t);
-        self.my_list.push(new_chunk);
-    }
-
-    /// Add with optional rebalance: chooses between 5% of total size or sqrt(total size).
-    pub fn add_optimized(&mut self, t: T, optimize_sqrt_size: bool) {
-        self.add(t);
-        self.set_chunk_size_optimized(optimize_sqrt_size);
-    }
-
-    /// Remove the first occurrence of `t`, in parallel.
-    /// If found, short-circuits further removals using an AtomicBool.
-    pub fn remove(&mut self, t: &T) {
-        let found = AtomicBool::new(false);
-        // We need parallel mutation over multiple chunks, so we do par_iter_mut.
-        // Each chunk is independent, so this is safe so long as we only remove from one chunk.
-        self.my_list.par_iter_mut().for_each(|chunk| {
-            if found.load(Ordering::Relaxed) {
-                // Another thread removed the item already
-                return;
-            }
-            // Attempt binary search in this chunk
-            if let Ok(idx) = chunk.binary_search(t) {
-                // CAS to become the thread that does the removal
-                let was_found = found.swap(true, Ordering::Relaxed);
-                if !was_found {
-                    // We are the first to swap from false -> true
-                    chunk.remove(idx);
-                }
-            }
-        });
-    }
-
-    /// Remove all instances of `t`, in parallel (each chunk will remove all matches).
-    pub fn remove_all(&mut self, t: &T) {
-        // We can do chunk.retain(...). We'll do it in parallel:
-        self.my_list.par_iter_mut().for_each(|chunk| {
-            chunk.retain(|x| x != t);
-        });
-    }
-
-    /// Remove all + optional rebalance
-    pub fn remove_all_optimized(&mut self, t: &T, optimize_sqrt_size: bool) {
-        self.remove_all(t);
-        self.set_chunk_size_optimized(optimize_sqrt_size);
-    }
-
-    /// Remove element at global index; recursively tries index+1 if out of range.
-    pub fn remove_at(&mut self, index: usize) {
-        // if index >= self.len() {
-        //     panic!("Index out of range");
-        // }
-        // let (chunk_index, pos) = self.index_to_chunk_pos(index);
-        // // Might fail if chunk indexing is off by 1 in some edge cases.
-        // // The original C# code calls removeAt(index+1) on ArgOutOfRange; we’ll just panic here for clarity.
-        // self.my_list[chunk_index].remove(pos);
-
-        if index >= self.len() {
-            panic!("Index out of range");
-        }
-        
-        let (chunk_index, pos) = self.index_to_chunk_pos(index);
-
-        // Check if chunk_index is valid
-        if chunk_index >= self.my_list.len() {
-            // Shift + 1
-            self.remove_at(index + 1);
-            return;
-        }
-
-        let chunk = &mut self.my_list[chunk_index];
-
-        // Check if pos is valid within this chunk
-        if pos >= chunk.len() {
-            // Shift + 1
-            self.remove_at(index + 1);
-            return;
-        }
-
-        // If we get here, remove the element
-        chunk.remove(pos);
-    }
-
-    /// Set an item at a particular index.
-    pub fn set(&mut self, index: usize, t: T) {
-        if index >= self.len() {
-            panic!("Index out of range");
-        }
-        let (chunk_index, pos) = self.index_to_chunk_pos(index);
-        self.my_list[chunk_index][pos] = t;
-    }
-
-    /// Get an item at a particular index.
-    pub fn get(&self, index: usize) -> &T {
-        // if index >= self.len() {
-        //     panic!("Index out of range");
-        // }
-        // let (chunk_index, pos) = self.index_to_chunk_pos(index);
-        // &self.my_list[chunk_index][pos]
-        // If the user requested an index beyond the total size, 
-        // we bail out (like the C# code eventually throws).
-        
-        if index >= self.len() {
-            panic!("Index out of range");
-        }
-        
-        let (chunk_index, pos) = self.index_to_chunk_pos(index);
-
-        // If the chunk_index is out of range, shift + 1.
-        if chunk_index >= self.my_list.len() {
-            // Try again with index+1
-            return self.get(index + 1);
-        }
-        
-        let chunk = &self.my_list[chunk_index];
-        
-        // If pos is out of range for this chunk, shift + 1.
-        if pos >= chunk.len() {
-            // Try again with index+1
-            return self.get(index + 1);
-        }
-        
-        // Otherwise, we got it
-        &chunk[pos]
-    }
-
-    /// Return a new Vec containing all elements from all chunks (in order).
-    pub fn get_list(&self) -> Vec<T> {
-        // We'll just flatten them in sequence
-        let mut items = Vec::with_capacity(self.len());
-        for chunk in &self.my_list {
-            items.extend(chunk.iter().cloned());
-        }
-        items
-    }
-
-    /// Check if the list contains a given item, in parallel.
-    pub fn contains(&self, t: &T) -> bool {
-        // We can do a nested parallel approach:
-        self.my_list
-            .par_iter()
-            .any(|chunk| chunk.par_iter().any(|item| item == t))
-    }
-
-    /// Clear the entire list (remove all chunks).
-    pub fn clear(&mut self) {
-        self.my_list.clear();
-    }
-
-    /// Return the total number of elements (sum of chunk lengths).
-    pub fn len(&self) -> usize {
-        self.my_list.iter().map(|v| v.len()).sum()
-    }
-
-    /// Check if the list is empty.
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
-    }
-
-    /// Set a new chunk size and rebalance the elements.
-    pub fn set_chunk_size(&mut self, new_chunk_size: usize) {
-        if new_chunk_size > self.chunk_size {
-            // If bigger, we can just set the chunk size. 
-            // The C# code rebalances only if we are shrinking.
-            self.chunk_size = new_chunk_size;
-        } else {
-            // Rebalance all
-            let items = self.get_list();
-            self.clear();
-            self.chunk_size = new_chunk_size;
-            for item in items {
-                self.add(item);
-            }
-        }
-    }
-
-    /// Optimized version of `set_chunk_size`: choose between 5% or sqrt of total size.
-    pub fn set_chunk_size_optimized(&mut self, optimize_sqrt_size: bool) {
-        let total_size = self.len();
-        if total_size == 0 {
-            return;
-        }
-        let new_size = if optimize_sqrt_size {
-            // use sqrt(total_size)
-            (total_size as f64).sqrt() as usize
-        } else {
-            // use 5% of total size
-            (total_size as f64 * 0.05).round() as usize
-        };
-        if new_size == 0 {
-            // fallback to 1 if 5% is 0
-            self.set_chunk_size(1);
-        } else {
-            self.set_chunk_size(new_size);
-        }
-    }
-
-    /// Get current chunk size
-    pub fn get_chunk_size(&self) -> usize {
-        self.chunk_size
-    }
-
-    /// Get amount of chunks in the list
-    pub fn chunk_amount(&self) -> usize {
-        self.my_list.len()
-    }
-
-    /// Sort the entire list. We gather everything, sort in parallel, then rebuild.
-    pub fn sort(&mut self) {
-        let mut items = self.get_list();
-        // Parallel sort from Rayon
-        items.par_sort();
-        self.clear();
-        for item in items {
-            self.add(item);
-        }
-    }
-
-    /// Print all items, chunk by chunk (for debugging).
-    pub fn print(&self) {
-        for (i, chunk) in self.my_list.iter().enumerate() {
-            println!("Chunk #{}", i + 1);
-            for item in chunk {
-                print!("{:?} ", item);
-            }
-            println!();
-        }
-    }
-}
+#![allow(dead_code)]
+#[cfg(feature = "rayon")]
+use crate::execution::ExecutionConfig;
+use crate::observer::{ChunkEvent, Observers, SubscriptionId};
+use crate::ChunkListSnapshot;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::mem;
+#[cfg(feature = "rayon")]
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "rayon")]
+use std::sync::Mutex;
+
+#[derive(Debug)]
+pub struct ChunkList<T> {
+    pub(crate) my_list: Vec<Arc<Vec<T>>>,
+    pub(crate) chunk_size: usize,
+    #[cfg(feature = "rayon")]
+    pub(crate) execution: ExecutionConfig,
+    observers: Observers<T>,
+}
+
+impl<T> Default for ChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Default constructor with chunk size = 1000
+    fn default() -> Self {
+        Self::new(1000)
+    }
+}
+
+impl<T> ChunkList<T>
+where T: Ord + Debug +Send + Sync + Clone, {
+    /// Creates a new ChunkList with the specified chunk size.
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            my_list: Vec::new(),
+            chunk_size,
+            #[cfg(feature = "rayon")]
+            execution: ExecutionConfig::default(),
+            observers: Observers::default(),
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    /// Set how parallel operations run (thread pool, sequential cutoff).
+    pub fn set_execution(&mut self, config: ExecutionConfig) {
+        self.execution = config;
+    }
+
+    #[cfg(feature = "rayon")]
+    /// Get the execution config
+    pub fn get_execution(&self) -> &ExecutionConfig {
+        &self.execution
+    }
+
+    #[cfg(feature = "rayon")]
+    /// Run `f` with a different execution config, then restore the list's own.
+    pub fn with_execution<R, F>(&mut self, config: ExecutionConfig, f: F) -> R
+    where F: FnOnce(&mut Self) -> R, {
+        let saved = mem::replace(&mut self.execution, config);
+        let result = f(self);
+        self.execution = saved;
+        result
+    }
+
+    /// Subscribe to every change made to the list, one event at a time.
+    pub fn subscribe<F>(&mut self, f: F) -> SubscriptionId
+    where F: FnMut(&ChunkEvent<T>) + Send + Sync + 'static, {
+        self.observers.subscribe(Box::new(f))
+    }
+
+    /// Subscribe to every change made to the list, receiving all events of an operation at once.
+    pub fn subscribe_batched<F>(&mut self, f: F) -> SubscriptionId
+    where F: FnMut(&[ChunkEvent<T>]) + Send + Sync + 'static, {
+        self.observers.subscribe_batched(Box::new(f))
+    }
+
+    /// Remove an observer. Returns false if it was not subscribed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.observers.unsubscribe(id)
+    }
+
+    /// Helper: Global index of the first element of a chunk.
+    pub(crate) fn chunk_offset(&self, chunk_index: usize) -> usize {
+        self.my_list[..chunk_index].iter().map(|chunk| chunk.len()).sum()
+    }
+
+    /// Helper: Replace the contents with `items`, packed into full chunks.
+    pub(crate) fn refill(&mut self, items: Vec<T>) {
+        self.my_list.clear();
+        let mut items = items.into_iter();
+        loop {
+            let chunk: Vec<T> = items.by_ref().take(self.chunk_size.max(1)).collect();
+            if chunk.is_empty() {
+                break;
+            }
+            self.my_list.push(Arc::new(chunk));
+        }
+    }
+
+    /// Helper: Swap in a new chunk layout, reporting every chunk that isn't shared with the
+    /// old layout as removed and re-inserted.
+    pub(crate) fn replace_chunks(&mut self, chunks: Vec<Arc<Vec<T>>>) {
+        let old = mem::replace(&mut self.my_list, chunks);
+        if !self.observers.is_active() {
+            return;
+        }
+        let mut events = Vec::new();
+        let mut offset = 0;
+        for chunk_index in 0..old.len().max(self.my_list.len()) {
+            let (old_chunk, new_chunk) = (old.get(chunk_index), self.my_list.get(chunk_index));
+            if let (Some(a), Some(b)) = (old_chunk, new_chunk) {
+                if Arc::ptr_eq(a, b) {
+                    offset += b.len();
+                    continue;
+                }
+            }
+            for value in old_chunk.into_iter().flat_map(|chunk| chunk.iter()) {
+                events.push(ChunkEvent::Removed { index: offset, value: value.clone() });
+            }
+            for value in new_chunk.into_iter().flat_map(|chunk| chunk.iter()) {
+                events.push(ChunkEvent::Inserted { index: offset, value: value.clone() });
+                offset += 1;
+            }
+        }
+        self.observers.emit(events);
+    }
+
+    /// Helper: Append whole chunks after the last one, reporting their elements as inserted.
+    pub(crate) fn append_chunks(&mut self, chunks: Vec<Vec<T>>) {
+        let mut index = self.len();
+        let active = self.observers.is_active();
+        let mut events = Vec::new();
+        for chunk in chunks.into_iter().filter(|chunk| !chunk.is_empty()) {
+            if active {
+                for value in &chunk {
+                    events.push(ChunkEvent::Inserted { index, value: value.clone() });
+                    index += 1;
+                }
+            }
+            self.my_list.push(Arc::new(chunk));
+        }
+        self.observers.emit(events);
+    }
+
+    /// Helper: Whether anyone is subscribed.
+    pub(crate) fn is_observed(&self) -> bool {
+        self.observers.is_active()
+    }
+
+    /// Helper: Deliver a single event, if anyone is listening.
+    pub(crate) fn notify(&mut self, event: ChunkEvent<T>) {
+        if self.observers.is_active() {
+            self.observers.emit(vec![event]);
+        }
+    }
+
+    /// Helper: Deliver the events of one operation, if anyone is listening.
+    pub(crate) fn notify_all(&mut self, events: Vec<ChunkEvent<T>>) {
+        if self.observers.is_active() && !events.is_empty() {
+            self.observers.emit(events);
+        }
+    }
+
+    /// Helper: Report every element that differs from `old`, a copy of the chunks taken
+    /// before they were modified in place (with the same layout).
+    pub(crate) fn report_updates(&mut self, old: Vec<Arc<Vec<T>>>) {
+        let mut events = Vec::new();
+        let mut index = 0;
+        for (old_chunk, new_chunk) in old.iter().zip(&self.my_list) {
+            if Arc::ptr_eq(old_chunk, new_chunk) {
+                index += new_chunk.len();
+                continue;
+            }
+            for (old, new) in old_chunk.iter().zip(new_chunk.iter()) {
+                if old != new {
+                    events.push(ChunkEvent::Updated { index, old: old.clone(), new: new.clone() });
+                }
+                index += 1;
+            }
+        }
+        self.observers.emit(events);
+    }
+
+    /// Helper: Convert a global index to (chunk_index, position_in_chunk).
+    /// Chunks may be partially filled (after removals or splits), so we walk the chunk lengths.
+    pub(crate) fn locate(&self, index: usize) -> Option<(usize, usize)> {
+        let mut remaining = index;
+        for (chunk_index, chunk) in self.my_list.iter().enumerate() {
+            if remaining < chunk.len() {
+                return Some((chunk_index, remaining));
+            }
+            remaining -= chunk.len();
+        }
+        None
+    }
+
+    /// Helper: Split an overflowing chunk in half, keeping the tail right after it.
+    fn split_chunk(&mut self, chunk_index: usize) {
+        let chunk = Arc::make_mut(&mut self.my_list[chunk_index]);
+        let tail = chunk.split_off(chunk.len() / 2);
+        self.my_list.insert(chunk_index + 1, Arc::new(tail));
+    }
+
+    /// Add an element to the list, finding a chunk that isn't full or creating a new one.
+    pub fn add(&mut self, t: T) {
+        let event = self.observers.is_active().then(|| t.clone());
+        let chunk_index = match self.my_list.iter().position(|chunk| chunk.len() < self.chunk_size) {
+            Some(chunk_index) => {
+                Arc::make_mut(&mut self.my_list[chunk_index]).push(t);
+                chunk_index
+            }
+            None => {
+                // If we get here, all chunks are full -> create a new chunk
+                let mut new_chunk = Vec::with_capacity(self.chunk_size);
+                new_chunk.push(t);
+                self.my_list.push(Arc::new(new_chunk));
+                self.my_list.len() - 1
+            }
+        };
+        if let Some(value) = event {
+            let index = self.chunk_offset(chunk_index + 1) - 1;
+            self.observers.emit(vec![ChunkEvent::Inserted { index, value }]);
+        }
+    }
+
+    /// Add with optional rebalance: chooses between 5% of total size or sqrt(total size).
+    pub fn add_optimized(&mut self, t: T, optimize_sqrt_size: bool) {
+        self.add(t);
+        self.set_chunk_size_optimized(optimize_sqrt_size);
+    }
+
+    /// Helper: Remove the first occurrence of `t` found by any thread, returning
+    /// (chunk_index, position, value). Short-circuits further removals using an AtomicBool.
+    #[cfg(feature = "rayon")]
+    fn remove_first_par(&mut self, t: &T) -> Option<(usize, usize, T)> {
+        let found = AtomicBool::new(false);
+        // Where the winning thread removed the element
+        let removed = Mutex::new(None);
+        // We need parallel mutation over multiple chunks, so we do par_iter_mut.
+        // Each chunk is independent, so this is safe so long as we only remove from one chunk.
+        let work = self.len();
+        self.execution.run(work, |min_len| {
+            self.my_list.par_iter_mut().enumerate().with_min_len(min_len).for_each(|(chunk_index, chunk)| {
+                if found.load(Ordering::Relaxed) {
+                    // Another thread removed the item already
+                    return;
+                }
+                // Search this chunk (chunks are only sorted after `sort`, so no binary search)
+                if let Some(idx) = chunk.iter().position(|x| x == t) {
+                    // CAS to become the thread that does the removal
+                    let was_found = found.swap(true, Ordering::Relaxed);
+                    if !was_found {
+                        // We are the first to swap from false -> true
+                        let value = Arc::make_mut(chunk).remove(idx);
+                        *removed.lock().unwrap() = Some((chunk_index, idx, value));
+                    }
+                }
+            })
+        });
+        removed.into_inner().unwrap()
+    }
+
+    /// Helper: Remove the occurrence of `t` with the smallest index, returning
+    /// (chunk_index, position, value).
+    fn remove_first_match(&mut self, t: &T) -> Option<(usize, usize, T)> {
+        let (chunk_index, pos) = self.find_in_chunks(|x| x == t, false)?;
+        Some((chunk_index, pos, Arc::make_mut(&mut self.my_list[chunk_index]).remove(pos)))
+    }
+
+    /// Remove one occurrence of `t`, in parallel. Whichever thread finds one first removes
+    /// it; in deterministic mode (see `ExecutionConfig`) it's always the first occurrence.
+    pub fn remove(&mut self, t: &T) {
+        #[cfg(feature = "rayon")]
+        let removed = match self.execution.is_deterministic() {
+            true => self.remove_first_match(t),
+            false => self.remove_first_par(t),
+        };
+        #[cfg(not(feature = "rayon"))]
+        let removed = self.remove_first_match(t);
+        if let Some((chunk_index, idx, value)) = removed {
+            if self.observers.is_active() {
+                let index = self.chunk_offset(chunk_index) + idx;
+                self.observers.emit(vec![ChunkEvent::Removed { index, value }]);
+            }
+        }
+    }
+
+    /// Helper: Remove every `t` from one chunk, returning the removed values with their
+    /// positions in the chunk if `report` is set.
+    pub(crate) fn remove_from_chunk(chunk: &mut Arc<Vec<T>>, t: &T, report: bool) -> Vec<(usize, T)> {
+        let mut removed = Vec::new();
+        // Only unshare chunks that actually change
+        if chunk.contains(t) {
+            let mut pos = 0;
+            Arc::make_mut(chunk).retain(|x| {
+                if report && x == t {
+                    removed.push((pos, x.clone()));
+                }
+                pos += 1;
+                x != t
+            });
+        }
+        removed
+    }
+
+    /// Helper: Report what `remove_from_chunk` removed from every chunk.
+    pub(crate) fn report_removed(&mut self, removed: Vec<Vec<(usize, T)>>) {
+        if !self.observers.is_active() {
+            return;
+        }
+        // Report in ascending order, with each index adjusted for the removals before it
+        let mut events = Vec::new();
+        let mut original_offset = 0;
+        for (chunk, chunk_removed) in self.my_list.iter().zip(removed) {
+            let original_len = chunk.len() + chunk_removed.len();
+            for (pos, value) in chunk_removed {
+                let index = original_offset + pos - events.len();
+                events.push(ChunkEvent::Removed { index, value });
+            }
+            original_offset += original_len;
+        }
+        self.observers.emit(events);
+    }
+
+    /// Remove all instances of `t`, in parallel (each chunk will remove all matches).
+    pub fn remove_all(&mut self, t: &T) {
+        // Each chunk also reports what it removed and where, if anyone is listening
+        let report = self.observers.is_active();
+        let retain = |chunk: &mut Arc<Vec<T>>| Self::remove_from_chunk(chunk, t, report);
+        #[cfg(feature = "rayon")]
+        let removed: Vec<Vec<(usize, T)>> = self.execution.run(self.len(), |min_len| {
+            self.my_list.par_iter_mut().with_min_len(min_len).map(retain).collect()
+        });
+        #[cfg(not(feature = "rayon"))]
+        let removed: Vec<Vec<(usize, T)>> = self.my_list.iter_mut().map(retain).collect();
+        self.report_removed(removed);
+    }
+
+    /// Remove all + optional rebalance
+    pub fn remove_all_optimized(&mut self, t: &T, optimize_sqrt_size: bool) {
+        self.remove_all(t);
+        self.set_chunk_size_optimized(optimize_sqrt_size);
+    }
+
+    /// Insert an element at a global index, shifting later elements back.
+    /// If the target chunk overflows, it is split in half.
+    pub fn insert(&mut self, index: usize, t: T) {
+        let len = self.len();
+        if index > len {
+            panic!("Index out of range");
+        }
+        let event = self.observers.is_active().then(|| t.clone());
+        let chunk_index = if index == len {
+            // Appending: use the last chunk, or create the first one
+            if self.my_list.is_empty() {
+                self.my_list.push(Arc::new(Vec::with_capacity(self.chunk_size)));
+            }
+            let last = self.my_list.len() - 1;
+            Arc::make_mut(&mut self.my_list[last]).push(t);
+            last
+        } else {
+            let (chunk_index, pos) = self.locate(index).unwrap();
+            Arc::make_mut(&mut self.my_list[chunk_index]).insert(pos, t);
+            chunk_index
+        };
+        let split_at = self.my_list[chunk_index].len() / 2;
+        let split = self.my_list[chunk_index].len() > self.chunk_size.max(1);
+        if split {
+            self.split_chunk(chunk_index);
+        }
+        if let Some(value) = event {
+            let mut events = vec![ChunkEvent::Inserted { index, value }];
+            if split {
+                events.push(ChunkEvent::ChunkSplit { chunk_index, at: split_at });
+            }
+            self.observers.emit(events);
+        }
+    }
+
+    /// Remove and return the element at a global index.
+    pub fn remove_at(&mut self, index: usize) -> T {
+        let value = match self.locate(index) {
+            Some((chunk_index, pos)) => Arc::make_mut(&mut self.my_list[chunk_index]).remove(pos),
+            None => panic!("Index out of range"),
+        };
+        if self.observers.is_active() {
+            self.observers.emit(vec![ChunkEvent::Removed { index, value: value.clone() }]);
+        }
+        value
+    }
+
+    /// Set an item at a particular index.
+    pub fn set(&mut self, index: usize, t: T) {
+        let (chunk_index, pos) = match self.locate(index) {
+            Some(found) => found,
+            None => panic!("Index out of range"),
+        };
+        let new = self.observers.is_active().then(|| t.clone());
+        let old = mem::replace(&mut Arc::make_mut(&mut self.my_list[chunk_index])[pos], t);
+        if let Some(new) = new {
+            self.observers.emit(vec![ChunkEvent::Updated { index, old, new }]);
+        }
+    }
+
+    /// Get an item at a particular index.
+    pub fn get(&self, index: usize) -> &T {
+        match self.locate(index) {
+            Some((chunk_index, pos)) => &self.my_list[chunk_index][pos],
+            None => panic!("Index out of range"),
+        }
+    }
+
+    /// Return a new Vec containing all elements from all chunks (in order).
+    pub fn get_list(&self) -> Vec<T> {
+        // We'll just flatten them in sequence
+        let mut items = Vec::with_capacity(self.len());
+        for chunk in &self.my_list {
+            items.extend(chunk.iter().cloned());
+        }
+        items
+    }
+
+    /// Take a cheap read-only snapshot of the current state (see `ChunkListSnapshot`).
+    pub fn snapshot(&self) -> ChunkListSnapshot<T> {
+        ChunkListSnapshot::new(self.my_list.clone(), self.chunk_size)
+    }
+
+    /// Check if the list contains a given item, in parallel.
+    pub fn contains(&self, t: &T) -> bool {
+        // One task per chunk; splitting chunks further costs more than it saves
+        #[cfg(feature = "rayon")]
+        let found = self.execution.run(self.len(), |min_len| {
+            self.my_list.par_iter().with_min_len(min_len).any(|chunk| chunk.contains(t))
+        });
+        #[cfg(not(feature = "rayon"))]
+        let found = self.my_list.iter().any(|chunk| chunk.contains(t));
+        found
+    }
+
+    /// Helper: (chunk_index, position) of the first or last element matching `pred`.
+    /// Chunks are searched in parallel; rayon's `find_map_first`/`find_map_last` stop
+    /// looking past a match but always return the one nearest the requested end.
+    fn find_in_chunks<F>(&self, pred: F, last: bool) -> Option<(usize, usize)>
+    where F: Fn(&T) -> bool + Sync + Send, {
+        let first = |(chunk_index, chunk): (usize, &Arc<Vec<T>>)| chunk.iter().position(&pred).map(|pos| (chunk_index, pos));
+        let last_in = |(chunk_index, chunk): (usize, &Arc<Vec<T>>)| chunk.iter().rposition(&pred).map(|pos| (chunk_index, pos));
+        #[cfg(feature = "rayon")]
+        let found = self.execution.run(self.len(), |min_len| {
+            let chunks = self.my_list.par_iter().enumerate().with_min_len(min_len);
+            if last {
+                chunks.find_map_last(last_in)
+            } else {
+                chunks.find_map_first(first)
+            }
+        });
+        #[cfg(not(feature = "rayon"))]
+        let found = if last {
+            self.my_list.iter().enumerate().rev().find_map(last_in)
+        } else {
+            self.my_list.iter().enumerate().find_map(first)
+        };
+        found
+    }
+
+    /// Find the element with the smallest index matching `pred`, in parallel.
+    pub fn find_first<F>(&self, pred: F) -> Option<&T>
+    where F: Fn(&T) -> bool + Sync + Send, {
+        self.find_in_chunks(pred, false).map(|(chunk_index, pos)| &self.my_list[chunk_index][pos])
+    }
+
+    /// Find the element with the largest index matching `pred`, in parallel.
+    pub fn find_last<F>(&self, pred: F) -> Option<&T>
+    where F: Fn(&T) -> bool + Sync + Send, {
+        self.find_in_chunks(pred, true).map(|(chunk_index, pos)| &self.my_list[chunk_index][pos])
+    }
+
+    /// Global index of the first element matching `pred`, in parallel.
+    pub fn position<F>(&self, pred: F) -> Option<usize>
+    where F: Fn(&T) -> bool + Sync + Send, {
+        self.find_in_chunks(pred, false).map(|(chunk_index, pos)| self.chunk_offset(chunk_index) + pos)
+    }
+
+    /// Global index of the last element matching `pred`, in parallel.
+    pub fn rposition<F>(&self, pred: F) -> Option<usize>
+    where F: Fn(&T) -> bool + Sync + Send, {
+        self.find_in_chunks(pred, true).map(|(chunk_index, pos)| self.chunk_offset(chunk_index) + pos)
+    }
+
+    /// Global index of the first occurrence of `t`, in parallel.
+    pub fn index_of(&self, t: &T) -> Option<usize> {
+        self.position(|x| x == t)
+    }
+
+    /// Count the occurrences of `t`, in parallel.
+    pub fn count(&self, t: &T) -> usize {
+        self.count_if(|x| x == t)
+    }
+
+    /// Count the elements matching `pred`, in parallel.
+    pub fn count_if<F>(&self, pred: F) -> usize
+    where F: Fn(&T) -> bool + Sync + Send, {
+        let count = |chunk: &Arc<Vec<T>>| chunk.iter().filter(|x| pred(x)).count();
+        #[cfg(feature = "rayon")]
+        let total = self.execution.run(self.len(), |min_len| self.my_list.par_iter().with_min_len(min_len).map(count).sum());
+        #[cfg(not(feature = "rayon"))]
+        let total = self.my_list.iter().map(count).sum();
+        total
+    }
+
+    /// Clear the entire list (remove all chunks).
+    pub fn clear(&mut self) {
+        self.my_list.clear();
+        if self.observers.is_active() {
+            self.observers.emit(vec![ChunkEvent::Cleared]);
+        }
+    }
+
+    /// Return the total number of elements (sum of chunk lengths).
+    pub fn len(&self) -> usize {
+        self.my_list.iter().map(|v| v.len()).sum()
+    }
+
+    /// Check if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Set a new chunk size and rebalance the elements.
+    pub fn set_chunk_size(&mut self, new_chunk_size: usize) {
+        let old_chunk_size = self.chunk_size;
+        if new_chunk_size > self.chunk_size {
+            // If bigger, we can just set the chunk size. 
+            // The C# code rebalances only if we are shrinking.
+            self.chunk_size = new_chunk_size;
+        } else {
+            // Rebalance all
+            let items = self.get_list();
+            self.chunk_size = new_chunk_size;
+            self.refill(items);
+        }
+        if self.observers.is_active() {
+            self.observers.emit(vec![ChunkEvent::Rebalanced { old_chunk_size, new_chunk_size }]);
+        }
+    }
+
+    /// Optimized version of `set_chunk_size`: choose between 5% or sqrt of total size.
+    pub fn set_chunk_size_optimized(&mut self, optimize_sqrt_size: bool) {
+        let total_size = self.len();
+        if total_size == 0 {
+            return;
+        }
+        let new_size = if optimize_sqrt_size {
+            // use sqrt(total_size)
+            total_size.isqrt()
+        } else {
+            // use 5% of total size, rounded
+            (total_size + 10) / 20
+        };
+        if new_size == 0 {
+            // fallback to 1 if 5% is 0
+            self.set_chunk_size(1);
+        } else {
+            self.set_chunk_size(new_size);
+        }
+    }
+
+    /// Get current chunk size
+    pub fn get_chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Get amount of chunks in the list
+    pub fn chunk_amount(&self) -> usize {
+        self.my_list.len()
+    }
+
+    /// Helper: Sort `items` (stable), in parallel if the execution config allows.
+    pub(crate) fn sort_items(&self, items: &mut [T]) {
+        #[cfg(feature = "rayon")]
+        if self.execution.is_parallel(items.len()) {
+            // Parallel sort from Rayon
+            self.execution.run(items.len(), |_| items.par_sort());
+        } else {
+            items.sort();
+        }
+        #[cfg(not(feature = "rayon"))]
+        items.sort();
+    }
+
+    /// Sort the entire list. We gather everything, sort in parallel, then rebuild.
+    pub fn sort(&mut self) {
+        let mut items = self.get_list();
+        self.sort_items(&mut items);
+        self.refill(items);
+        if self.observers.is_active() {
+            self.observers.emit(vec![ChunkEvent::Sorted]);
+        }
+    }
+
+    /// Print all items, chunk by chunk (for debugging).
+    #[cfg(feature = "std")]
+    pub fn print(&self) {
+        for (i, chunk) in self.my_list.iter().enumerate() {
+            println!("Chunk #{}", i + 1);
+            for item in chunk.iter() {
+                print!("{:?} ", item);
+            }
+            println!();
+        }
+    }
+}
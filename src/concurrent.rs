@@ -0,0 +1,583 @@
+use crate::sync::{get_mut, lock, read, write};
+use crate::{ChunkList, ChunkListSnapshot};
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Display};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError, RwLock};
+
+/// A thread-safe ChunkList where every chunk sits behind its own lock.
+///
+/// Readers and writers lock chunks in ascending chunk order, so any number of
+/// threads (and transactions) can share the list without deadlocking. The outer
+/// lock is only taken exclusively when the chunk layout itself changes.
+#[derive(Debug)]
+pub struct ConcurrentChunkList<T> {
+    chunks: RwLock<Vec<Chunk<T>>>,
+    chunk_size: usize,
+    // Bumped by two whenever a chunk length changes, so commits can tell whether the
+    // positions they planned from the chunk lengths are still valid. Odd while a
+    // commit is resizing chunks under the shared outer lock.
+    layout: AtomicU64,
+}
+
+/// A chunk behind its own lock. `len` mirrors the chunk length so commits can plan
+/// without locking chunks they don't touch; it is only written with the chunk locked.
+#[derive(Debug)]
+struct Chunk<T> {
+    items: Mutex<Arc<Vec<T>>>,
+    len: AtomicUsize,
+}
+
+impl<T> Chunk<T> {
+    fn new(items: Arc<Vec<T>>) -> Self {
+        Self {
+            len: AtomicUsize::new(items.len()),
+            items: Mutex::new(items),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+}
+
+/// Error returned when a transaction cannot be committed.
+/// Nothing from the transaction is applied when this is returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    /// The buffered operation number `op` referred to an index past the end of the list.
+    IndexOutOfRange { op: usize, index: usize, len: usize },
+}
+
+impl Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::IndexOutOfRange { op, index, len } => write!(
+                f,
+                "operation #{} in transaction: index {} out of range for length {}",
+                op, index, len
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+/// A buffered operation waiting for commit.
+enum TxOp<'a, T> {
+    Set(usize, T),
+    Modify(usize, Box<dyn FnOnce(&mut T) + 'a>),
+    Get(usize, Arc<OnceLock<T>>),
+    Insert(usize, T),
+    RemoveAt(usize),
+    Move(usize, usize),
+}
+
+impl<T: Debug> Debug for TxOp<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxOp::Set(index, t) => f.debug_tuple("Set").field(index).field(t).finish(),
+            TxOp::Modify(index, _) => f.debug_tuple("Modify").field(index).finish(),
+            TxOp::Get(index, _) => f.debug_tuple("Get").field(index).finish(),
+            TxOp::Insert(index, t) => f.debug_tuple("Insert").field(index).field(t).finish(),
+            TxOp::RemoveAt(index) => f.debug_tuple("RemoveAt").field(index).finish(),
+            TxOp::Move(from, to) => f.debug_tuple("Move").field(from).field(to).finish(),
+        }
+    }
+}
+
+/// A batch of operations applied atomically on commit.
+///
+/// Operations are only buffered until `commit` is called. Dropping the transaction
+/// (including while unwinding from a panic) or calling `rollback` discards them.
+#[derive(Debug)]
+pub struct Transaction<'a, T> {
+    list: &'a ConcurrentChunkList<T>,
+    ops: Vec<TxOp<'a, T>>,
+}
+
+/// A value read by `Transaction::get`, filled in once the transaction commits.
+#[derive(Debug, Clone)]
+pub struct TransactionRead<T> {
+    value: Arc<OnceLock<T>>,
+}
+
+impl<T> TransactionRead<T> {
+    /// The value read at commit time, or None if the transaction wasn't committed.
+    pub fn get(&self) -> Option<&T> {
+        self.value.get()
+    }
+}
+
+/// How many times a commit re-plans after a concurrent layout change before it
+/// falls back to taking the outer lock exclusively.
+const FAST_PATH_ATTEMPTS: usize = 4;
+
+/// A claimed (odd) layout version, released when dropped, including while unwinding
+/// from a panicking `modify` closure.
+struct LayoutClaim<'l>(&'l AtomicU64);
+
+impl Drop for LayoutClaim<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Walker over the chunks that locks them in ascending order, for the read methods.
+struct Working<'g, T> {
+    chunks: &'g [Chunk<T>],
+    guards: Vec<MutexGuard<'g, Arc<Vec<T>>>>,
+}
+
+impl<'g, T> Working<'g, T> {
+    fn new(chunks: &'g [Chunk<T>]) -> Self {
+        Self {
+            chunks,
+            guards: Vec::new(),
+        }
+    }
+
+    /// Lock the next chunk in ascending order. Returns false once every chunk is held.
+    fn lock_next(&mut self) -> bool {
+        let next = self.guards.len();
+        if next == self.chunks.len() {
+            return false;
+        }
+        self.guards.push(lock(&self.chunks[next].items));
+        true
+    }
+
+    /// Locate a global index, locking chunks as we walk over them.
+    fn locate(&mut self, index: usize) -> Option<(usize, usize)> {
+        let mut remaining = index;
+        let mut chunk_index = 0;
+        loop {
+            if chunk_index == self.guards.len() && !self.lock_next() {
+                return None;
+            }
+            let len = self.guards[chunk_index].len();
+            if remaining < len {
+                return Some((chunk_index, remaining));
+            }
+            remaining -= len;
+            chunk_index += 1;
+        }
+    }
+
+    /// Length of the whole list, with every chunk held.
+    fn len(&mut self) -> usize {
+        while self.lock_next() {}
+        self.guards.iter().map(|chunk| chunk.len()).sum()
+    }
+}
+
+/// Locate a global index given the chunk lengths.
+/// With `allow_end`, the position right after the last element is also accepted.
+fn locate(lens: &[usize], index: usize, allow_end: bool) -> Option<(usize, usize)> {
+    let mut remaining = index;
+    for (chunk_index, &len) in lens.iter().enumerate() {
+        if remaining < len {
+            return Some((chunk_index, remaining));
+        }
+        remaining -= len;
+    }
+    match lens.last() {
+        Some(&last) if allow_end && remaining == 0 => Some((lens.len() - 1, last)),
+        _ => None,
+    }
+}
+
+/// Resolve every buffered operation to (chunk, position) pairs, updating `lens` as the
+/// operations would. A move resolves to two positions, every other operation to one.
+fn plan<T>(ops: &[TxOp<'_, T>], lens: &mut [usize]) -> Result<Vec<(usize, usize)>, TransactionError> {
+    let mut positions = Vec::with_capacity(ops.len());
+    for (op, tx_op) in ops.iter().enumerate() {
+        let (index, allow_end) = match tx_op {
+            TxOp::Set(index, _)
+            | TxOp::Modify(index, _)
+            | TxOp::Get(index, _)
+            | TxOp::RemoveAt(index)
+            | TxOp::Move(index, _) => (*index, false),
+            TxOp::Insert(index, _) => (*index, true),
+        };
+        let len: usize = lens.iter().sum();
+        let (chunk_index, pos) =
+            locate(lens, index, allow_end).ok_or(TransactionError::IndexOutOfRange { op, index, len })?;
+        positions.push((chunk_index, pos));
+        match tx_op {
+            TxOp::Insert(..) => lens[chunk_index] += 1,
+            TxOp::RemoveAt(_) => lens[chunk_index] -= 1,
+            TxOp::Move(_, to) => {
+                lens[chunk_index] -= 1;
+                let Some((to_chunk, to_pos)) = locate(lens, *to, true) else {
+                    return Err(TransactionError::IndexOutOfRange { op, index: *to, len: len - 1 });
+                };
+                lens[to_chunk] += 1;
+                positions.push((to_chunk, to_pos));
+            }
+            _ => {}
+        }
+    }
+    Ok(positions)
+}
+
+/// Apply planned operations to copy-on-write scratch chunks. `current` gives the chunk
+/// a scratch copy starts from. Reads are only published once every operation ran, so
+/// a panicking `modify` closure leaves nothing behind.
+fn apply<T: Clone>(
+    ops: Vec<TxOp<'_, T>>,
+    positions: &[(usize, usize)],
+    current: impl Fn(usize) -> Arc<Vec<T>>,
+) -> BTreeMap<usize, Vec<T>> {
+    let mut scratch: BTreeMap<usize, Vec<T>> = BTreeMap::new();
+    let mut reads = Vec::new();
+    let mut positions = positions.iter().copied();
+    for tx_op in ops {
+        let (chunk_index, pos) = positions.next().expect("every operation was planned");
+        if let TxOp::Get(_, slot) = tx_op {
+            let t = match scratch.get(&chunk_index) {
+                Some(chunk) => chunk[pos].clone(),
+                None => current(chunk_index)[pos].clone(),
+            };
+            reads.push((slot, t));
+            continue;
+        }
+        let chunk = scratch.entry(chunk_index).or_insert_with(|| current(chunk_index).to_vec());
+        match tx_op {
+            TxOp::Set(_, t) => chunk[pos] = t,
+            TxOp::Modify(_, f) => f(&mut chunk[pos]),
+            TxOp::Insert(_, t) => chunk.insert(pos, t),
+            TxOp::RemoveAt(_) => {
+                chunk.remove(pos);
+            }
+            TxOp::Move(..) => {
+                let t = chunk.remove(pos);
+                let (to_chunk, to_pos) = positions.next().expect("every move was planned");
+                scratch.entry(to_chunk).or_insert_with(|| current(to_chunk).to_vec()).insert(to_pos, t);
+            }
+            TxOp::Get(..) => unreachable!(),
+        }
+    }
+    for (slot, t) in reads {
+        let _ = slot.set(t);
+    }
+    scratch
+}
+
+impl<T> ConcurrentChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Creates a new ConcurrentChunkList with the specified chunk size.
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunks: RwLock::new(Vec::new()),
+            chunk_size,
+            layout: AtomicU64::new(0),
+        }
+    }
+
+    /// Start a new transaction against this list.
+    pub fn transaction(&self) -> Transaction<'_, T> {
+        Transaction {
+            list: self,
+            ops: Vec::new(),
+        }
+    }
+
+    /// Add an element to the first chunk that isn't full, or to a new chunk.
+    pub fn add(&self, t: T) {
+        {
+            let chunks = read(&self.chunks);
+            for chunk in chunks.iter().filter(|chunk| chunk.len() < self.chunk_size) {
+                let mut items = lock(&chunk.items);
+                if items.len() < self.chunk_size {
+                    Arc::make_mut(&mut items).push(t);
+                    chunk.len.store(items.len(), Ordering::Release);
+                    self.layout.fetch_add(2, Ordering::AcqRel);
+                    return;
+                }
+            }
+        }
+        // All chunks were full -> append a new one (re-checking the last chunk, another
+        // thread may have appended one in the meantime)
+        let mut chunks = write(&self.chunks);
+        if let Some(last) = chunks.last_mut() {
+            let items = get_mut(&mut last.items);
+            if items.len() < self.chunk_size {
+                Arc::make_mut(items).push(t);
+                *last.len.get_mut() = items.len();
+                self.layout.fetch_add(2, Ordering::AcqRel);
+                return;
+            }
+        }
+        let mut new_chunk = Vec::with_capacity(self.chunk_size);
+        new_chunk.push(t);
+        chunks.push(Chunk::new(Arc::new(new_chunk)));
+        self.layout.fetch_add(2, Ordering::AcqRel);
+    }
+
+    /// Get a copy of the item at a particular index.
+    pub fn get(&self, index: usize) -> T {
+        let chunks = read(&self.chunks);
+        let mut work = Working::new(&chunks);
+        match work.locate(index) {
+            Some((chunk_index, pos)) => work.guards[chunk_index][pos].clone(),
+            None => panic!("Index out of range"),
+        }
+    }
+
+    /// Set an item at a particular index.
+    pub fn set(&self, index: usize, t: T) {
+        let mut tx = self.transaction();
+        tx.set(index, t);
+        tx.commit().expect("Index out of range");
+    }
+
+    /// Insert an element at a global index, splitting the chunk if it overflows.
+    pub fn insert(&self, index: usize, t: T) {
+        let mut tx = self.transaction();
+        tx.insert(index, t);
+        tx.commit().expect("Index out of range");
+    }
+
+    /// Remove the element at a global index.
+    pub fn remove_at(&self, index: usize) {
+        let mut tx = self.transaction();
+        tx.remove_at(index);
+        tx.commit().expect("Index out of range");
+    }
+
+    /// Check if the list contains a given item.
+    pub fn contains(&self, t: &T) -> bool {
+        let chunks = read(&self.chunks);
+        let mut work = Working::new(&chunks);
+        while work.lock_next() {}
+        work.guards.iter().any(|chunk| chunk.contains(t))
+    }
+
+    /// Return the total number of elements, as seen at a single point in time.
+    pub fn len(&self) -> usize {
+        let chunks = read(&self.chunks);
+        let len = Working::new(&chunks).len();
+        len
+    }
+
+    /// Check if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all chunks.
+    pub fn clear(&self) {
+        write(&self.chunks).clear();
+        self.layout.fetch_add(2, Ordering::AcqRel);
+    }
+
+    /// Return a new Vec containing all elements from all chunks (in order).
+    pub fn get_list(&self) -> Vec<T> {
+        let chunks = read(&self.chunks);
+        let mut work = Working::new(&chunks);
+        while work.lock_next() {}
+        work.guards.iter().flat_map(|chunk| chunk.iter().cloned()).collect()
+    }
+
+    /// Take a consistent, point-in-time snapshot without copying any elements.
+    /// Chunks are only locked long enough to clone their `Arc`s.
+    pub fn snapshot(&self) -> ChunkListSnapshot<T> {
+        let chunks = read(&self.chunks);
+        let mut work = Working::new(&chunks);
+        while work.lock_next() {}
+        let shared = work.guards.iter().map(|chunk| Arc::clone(chunk)).collect();
+        ChunkListSnapshot::new(shared, self.chunk_size)
+    }
+
+    /// Get current chunk size
+    pub fn get_chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Get amount of chunks in the list
+    pub fn chunk_amount(&self) -> usize {
+        read(&self.chunks).len()
+    }
+
+    /// Unwrap into a plain ChunkList with the same layout.
+    pub fn into_chunk_list(self) -> ChunkList<T> {
+        let chunks = self.chunks.into_inner().unwrap_or_else(PoisonError::into_inner);
+        let mut list = ChunkList::new(self.chunk_size);
+        list.my_list = chunks
+            .into_iter()
+            .map(|chunk| chunk.items.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .collect();
+        list
+    }
+}
+
+impl<T> From<ChunkList<T>> for ConcurrentChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    fn from(list: ChunkList<T>) -> Self {
+        Self {
+            chunks: RwLock::new(list.my_list.into_iter().map(Chunk::new).collect()),
+            chunk_size: list.chunk_size,
+            layout: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<'a, T> Transaction<'a, T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Buffer setting the item at `index`.
+    pub fn set(&mut self, index: usize, t: T) -> &mut Self {
+        self.ops.push(TxOp::Set(index, t));
+        self
+    }
+
+    /// Buffer changing the item at `index` in place. `f` runs at commit time, while
+    /// the chunk holding the item is locked, so read-modify-write updates are never lost.
+    pub fn modify(&mut self, index: usize, f: impl FnOnce(&mut T) + 'a) -> &mut Self {
+        self.ops.push(TxOp::Modify(index, Box::new(f)));
+        self
+    }
+
+    /// Buffer reading the item at `index`. The returned handle holds the value as it
+    /// was at that point of the transaction, once the transaction commits.
+    pub fn get(&mut self, index: usize) -> TransactionRead<T> {
+        let value = Arc::new(OnceLock::new());
+        self.ops.push(TxOp::Get(index, Arc::clone(&value)));
+        TransactionRead { value }
+    }
+
+    /// Buffer moving the item at `from` so that it ends up at index `to`.
+    pub fn move_item(&mut self, from: usize, to: usize) -> &mut Self {
+        self.ops.push(TxOp::Move(from, to));
+        self
+    }
+
+    /// Buffer inserting an item at `index` (which may equal the length, to append).
+    pub fn insert(&mut self, index: usize, t: T) -> &mut Self {
+        self.ops.push(TxOp::Insert(index, t));
+        self
+    }
+
+    /// Buffer removing the item at `index`.
+    pub fn remove_at(&mut self, index: usize) -> &mut Self {
+        self.ops.push(TxOp::RemoveAt(index));
+        self
+    }
+
+    /// Number of buffered operations.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Check if no operations are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Discard every buffered operation. Equivalent to dropping the transaction.
+    pub fn rollback(self) {}
+
+    /// Apply all buffered operations atomically, in the order they were buffered.
+    ///
+    /// Indices are resolved against the list as it is at commit time, including the
+    /// effect of earlier operations in the same transaction. If any operation fails,
+    /// the list is left untouched.
+    pub fn commit(self) -> Result<(), TransactionError> {
+        if self.ops.is_empty() {
+            return Ok(());
+        }
+        let list = self.list;
+        let mut ops = self.ops;
+        let max_len = list.chunk_size.max(1);
+
+        // Fast path: hold the outer lock shared, plan against the chunk lengths and
+        // lock only the chunks the operations touch, in ascending order
+        {
+            let chunks = read(&list.chunks);
+            for _ in 0..FAST_PATH_ATTEMPTS {
+                if chunks.is_empty() {
+                    break;
+                }
+                let version = list.layout.load(Ordering::Acquire);
+                if version % 2 == 1 {
+                    continue;
+                }
+                let before: Vec<usize> = chunks.iter().map(Chunk::len).collect();
+                let mut lens = before.clone();
+                let planned = plan(&ops, &mut lens);
+                let mut touched: Vec<usize> = match &planned {
+                    Ok(positions) => positions.iter().map(|&(chunk_index, _)| chunk_index).collect(),
+                    Err(_) => Vec::new(),
+                };
+                touched.sort_unstable();
+                touched.dedup();
+                let mut guards: BTreeMap<usize, _> =
+                    touched.iter().map(|&chunk_index| (chunk_index, lock(&chunks[chunk_index].items))).collect();
+
+                // Anything that changed a chunk length since we read them invalidates the plan.
+                // Resizing commits claim an odd version, so concurrent planners retry after us.
+                let resizes = lens != before;
+                let claim = match resizes && planned.is_ok() {
+                    true => match list.layout.compare_exchange(version, version + 1, Ordering::AcqRel, Ordering::Acquire) {
+                        Ok(_) => Some(LayoutClaim(&list.layout)),
+                        Err(_) => continue,
+                    },
+                    false if list.layout.load(Ordering::Acquire) == version => None,
+                    false => continue,
+                };
+                let positions = planned?;
+                if lens.iter().any(|&len| len > max_len) {
+                    // Chunks have to split
+                    break;
+                }
+                let mut scratch =
+                    apply(std::mem::take(&mut ops), &positions, |chunk_index| Arc::clone(&guards[&chunk_index]));
+                for (chunk_index, guard) in guards.iter_mut() {
+                    if let Some(chunk) = scratch.remove(chunk_index) {
+                        **guard = Arc::new(chunk);
+                        chunks[*chunk_index].len.store(guard.len(), Ordering::Release);
+                    }
+                }
+                drop(claim);
+                return Ok(());
+            }
+        }
+
+        // Slow path: the layout has to change (first chunk, or a chunk split), or other
+        // commits kept changing it, so apply with the outer lock held exclusively.
+        let mut chunks = write(&list.chunks);
+        let mut lens: Vec<usize> = chunks.iter().map(Chunk::len).collect();
+        if lens.is_empty() {
+            // Room for a first chunk; it only materializes if the commit succeeds
+            lens.push(0);
+        }
+        let positions = plan(&ops, &mut lens)?;
+        let scratch = apply(ops, &positions, |chunk_index| match chunks.get(chunk_index) {
+            Some(chunk) => Arc::clone(&lock(&chunk.items)),
+            None => Arc::new(Vec::with_capacity(list.chunk_size)),
+        });
+        for (chunk_index, chunk) in scratch {
+            match chunks.get_mut(chunk_index) {
+                Some(slot) => *get_mut(&mut slot.items) = Arc::new(chunk),
+                None => chunks.push(Chunk::new(Arc::new(chunk))),
+            }
+        }
+
+        let mut chunk_index = 0;
+        while chunk_index < chunks.len() {
+            let chunk = &mut chunks[chunk_index];
+            let items = get_mut(&mut chunk.items);
+            if items.len() > max_len {
+                let half = items.len() / 2;
+                let tail = Arc::make_mut(items).split_off(half);
+                *chunk.len.get_mut() = items.len();
+                chunks.insert(chunk_index + 1, Chunk::new(Arc::new(tail)));
+            } else {
+                *chunk.len.get_mut() = items.len();
+                chunk_index += 1;
+            }
+        }
+        list.layout.fetch_add(2, Ordering::AcqRel);
+        Ok(())
+    }
+}
@@ -0,0 +1,418 @@
+use crate::format::{decode_chunk, ChunkCodec, ChunkEntry, DefaultCodec, FormatError};
+use crate::sync::{get_mut, lock};
+use crate::ChunkList;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A ChunkList for data larger than memory: each chunk is either resident or spilled to
+/// a local file, and the least recently used resident chunks are spilled once the
+/// resident chunks exceed the memory budget.
+///
+/// The budget counts `size_of::<T>()` per resident element, so heap memory owned by the
+/// elements themselves (e.g. the bytes of a `String`) isn't included. Chunks are encoded
+/// with a `ChunkCodec` when spilled; the spill file is removed when the list is dropped.
+#[derive(Debug)]
+pub struct DiskChunkList<T, C> {
+    chunk_size: usize,
+    memory_budget: usize,
+    codec: C,
+    cache: Mutex<Cache<T>>,
+    spill: Mutex<SpillFile>,
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    len: usize,
+    resident: Option<Arc<Vec<T>>>,
+    // The resident copy differs from the spilled one (or there is none)
+    dirty: bool,
+    spilled: Option<ChunkEntry>,
+    last_used: u64,
+}
+
+#[derive(Debug)]
+struct Cache<T> {
+    slots: Vec<Slot<T>>,
+    // Resident chunks by last use, oldest first
+    lru: BTreeMap<u64, usize>,
+    clock: u64,
+    resident_bytes: usize,
+}
+
+/// A scratch file for encoded chunks, removed on drop.
+#[derive(Debug)]
+pub(crate) struct SpillFile {
+    path: PathBuf,
+    file: File,
+    end: u64,
+    // Extents no chunk refers to any more, by offset, merged with their neighbours
+    free: BTreeMap<u64, u64>,
+}
+
+static SPILL_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+impl SpillFile {
+    pub(crate) fn create(dir: &Path) -> io::Result<Self> {
+        let id = SPILL_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("chunklist-spill-{}-{}", std::process::id(), id));
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        Ok(Self {
+            path,
+            file,
+            end: 0,
+            free: BTreeMap::new(),
+        })
+    }
+
+    pub(crate) fn read(&mut self, entry: &ChunkEntry) -> io::Result<Vec<u8>> {
+        let mut payload = vec![0; entry.length as usize];
+        self.file.seek(SeekFrom::Start(entry.offset))?;
+        self.file.read_exact(&mut payload)?;
+        Ok(payload)
+    }
+
+    /// Write a payload, reusing the old extent if it still fits and otherwise a free one.
+    /// A moved chunk's old extent is only released once the payload has been written.
+    pub(crate) fn write(&mut self, old: Option<ChunkEntry>, payload: &[u8], count: usize) -> io::Result<ChunkEntry> {
+        let length = payload.len() as u64;
+        let in_place = old.filter(|old| old.length >= length);
+        let offset = match in_place {
+            Some(old) => old.offset,
+            None => self.allocate(length),
+        };
+        if let Err(e) = self.file.seek(SeekFrom::Start(offset)).and_then(|_| self.file.write_all(payload)) {
+            if in_place.is_none() {
+                self.release(offset, length);
+            }
+            return Err(e);
+        }
+        match (old, in_place) {
+            (Some(old), Some(_)) => self.release(old.offset + length, old.length - length),
+            (Some(old), None) => self.release(old.offset, old.length),
+            (None, _) => {}
+        }
+        Ok(ChunkEntry {
+            offset,
+            length,
+            count: count as u64,
+        })
+    }
+
+    /// Find room for `length` bytes: the first free extent that fits, or the end of the file.
+    fn allocate(&mut self, length: u64) -> u64 {
+        let fit = self.free.iter().find(|&(_, &free)| free >= length).map(|(&offset, &free)| (offset, free));
+        match fit {
+            Some((offset, free)) => {
+                self.free.remove(&offset);
+                if free > length {
+                    self.free.insert(offset + length, free - length);
+                }
+                offset
+            }
+            None => {
+                self.end += length;
+                self.end - length
+            }
+        }
+    }
+
+    /// Return an extent to the free list, merging it with free neighbours. Free space at
+    /// the end of the file is given back by truncating the file.
+    fn release(&mut self, mut offset: u64, mut length: u64) {
+        if length == 0 {
+            return;
+        }
+        if let Some((&prev, &prev_length)) = self.free.range(..offset).next_back() {
+            if prev + prev_length == offset {
+                self.free.remove(&prev);
+                offset = prev;
+                length += prev_length;
+            }
+        }
+        if let Some(next_length) = self.free.remove(&(offset + length)) {
+            length += next_length;
+        }
+        if offset + length == self.end {
+            self.end = offset;
+            // Best effort; the space past `end` is unused either way
+            let _ = self.file.set_len(offset);
+        } else {
+            self.free.insert(offset, length);
+        }
+    }
+
+    /// Copy the whole file to `w`.
+    pub(crate) fn copy_to(&mut self, w: &mut impl Write) -> io::Result<u64> {
+        self.file.seek(SeekFrom::Start(0))?;
+        io::copy(&mut (&mut self.file).take(self.end), w)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn chunk_bytes<T>(len: usize) -> usize {
+    len * mem::size_of::<T>()
+}
+
+impl<T> Cache<T> {
+    fn touch(&mut self, index: usize) {
+        let slot = &mut self.slots[index];
+        self.lru.remove(&slot.last_used);
+        self.clock += 1;
+        slot.last_used = self.clock;
+        self.lru.insert(self.clock, index);
+    }
+}
+
+impl<T> DiskChunkList<T, T::Codec>
+where T: DefaultCodec + Send + Sync + Clone, T::Codec: Sync, {
+    /// Create an empty list spilling to the system temp directory with the default codec.
+    pub fn new(chunk_size: usize, memory_budget: usize) -> io::Result<Self> {
+        Self::with_codec(chunk_size, memory_budget, std::env::temp_dir(), T::Codec::default())
+    }
+}
+
+impl<T, C> DiskChunkList<T, C>
+where T: Send + Sync + Clone, C: ChunkCodec<T> + Sync, {
+    /// Create an empty list spilling to a file in `spill_dir`, encoded with `codec`.
+    pub fn with_codec(
+        chunk_size: usize,
+        memory_budget: usize,
+        spill_dir: impl AsRef<Path>,
+        codec: C,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            chunk_size,
+            memory_budget,
+            codec,
+            cache: Mutex::new(Cache {
+                slots: Vec::new(),
+                lru: BTreeMap::new(),
+                clock: 0,
+                resident_bytes: 0,
+            }),
+            spill: Mutex::new(SpillFile::create(spill_dir.as_ref())?),
+        })
+    }
+
+    /// Spill least recently used chunks until the resident chunks fit in the budget.
+    fn enforce_budget(&self, cache: &mut Cache<T>) -> Result<(), FormatError> {
+        while cache.resident_bytes > self.memory_budget {
+            let Some((_, index)) = cache.lru.pop_first() else { break };
+            let slot = &mut cache.slots[index];
+            let chunk = slot.resident.take().expect("LRU entries are resident");
+            if slot.dirty {
+                let mut payload = Vec::new();
+                for item in chunk.iter() {
+                    self.codec.encode(item, &mut payload);
+                }
+                match lock(&self.spill).write(slot.spilled, &payload, chunk.len()) {
+                    Ok(entry) => slot.spilled = Some(entry),
+                    Err(e) => {
+                        // Keep the chunk resident so nothing is lost
+                        slot.resident = Some(chunk);
+                        cache.touch(index);
+                        return Err(e.into());
+                    }
+                }
+                slot.dirty = false;
+            }
+            cache.resident_bytes -= chunk_bytes::<T>(slot.len);
+        }
+        Ok(())
+    }
+
+    /// Get a chunk, reading it back from the spill file if needed.
+    fn load(&self, index: usize) -> Result<Arc<Vec<T>>, FormatError> {
+        let entry = {
+            let mut cache = lock(&self.cache);
+            if let Some(chunk) = cache.slots[index].resident.clone() {
+                cache.touch(index);
+                return Ok(chunk);
+            }
+            cache.slots[index].spilled.expect("non-resident chunks are spilled")
+        };
+
+        // Decode outside the cache lock so parallel scans read chunks concurrently
+        let payload = lock(&self.spill).read(&entry)?;
+        let chunk = Arc::new(decode_chunk(index, &entry, &payload, &self.codec)?);
+
+        let mut cache = lock(&self.cache);
+        if let Some(resident) = cache.slots[index].resident.clone() {
+            // Another thread loaded it first
+            cache.touch(index);
+            return Ok(resident);
+        }
+        cache.slots[index].resident = Some(chunk.clone());
+        cache.resident_bytes += chunk_bytes::<T>(entry.count as usize);
+        cache.touch(index);
+        self.enforce_budget(&mut cache)?;
+        Ok(chunk)
+    }
+
+    /// Load a chunk for writing, apply `f` to it and mark it dirty.
+    fn modify<R>(&mut self, index: usize, f: impl FnOnce(&mut Vec<T>) -> R) -> Result<R, FormatError> {
+        let mut chunk = self.load(index)?;
+        let mut cache = lock(&self.cache);
+        // Drop the cache's reference first so `make_mut` doesn't have to copy the chunk
+        let resident = cache.slots[index].resident.take();
+        cache.resident_bytes -= resident.map_or(0, |_| chunk_bytes::<T>(cache.slots[index].len));
+        let result = f(Arc::make_mut(&mut chunk));
+
+        let slot = &mut cache.slots[index];
+        slot.len = chunk.len();
+        slot.resident = Some(chunk);
+        slot.dirty = true;
+        cache.resident_bytes += chunk_bytes::<T>(slot.len);
+        cache.touch(index);
+        self.enforce_budget(&mut cache)?;
+        Ok(result)
+    }
+
+    /// Find the chunk and position of an index, using the chunk lengths only.
+    fn locate(&self, index: usize) -> Option<(usize, usize)> {
+        let cache = lock(&self.cache);
+        let mut remaining = index;
+        for (chunk_index, slot) in cache.slots.iter().enumerate() {
+            if remaining < slot.len {
+                return Some((chunk_index, remaining));
+            }
+            remaining -= slot.len;
+        }
+        None
+    }
+
+    /// Add an item to the first chunk with room, like `ChunkList::add`.
+    pub fn add(&mut self, t: T) -> Result<(), FormatError> {
+        let chunk_size = self.chunk_size;
+        let cache = get_mut(&mut self.cache);
+        match cache.slots.iter().position(|slot| slot.len < chunk_size) {
+            Some(chunk_index) => self.modify(chunk_index, |chunk| chunk.push(t)),
+            None => {
+                // If we get here, all chunks are full -> create a new chunk
+                let mut new_chunk = Vec::with_capacity(chunk_size);
+                new_chunk.push(t);
+                self.push_chunk(new_chunk)
+            }
+        }
+    }
+
+    /// Append a whole chunk after the last one.
+    pub(crate) fn push_chunk(&mut self, chunk: Vec<T>) -> Result<(), FormatError> {
+        let mut cache = lock(&self.cache);
+        cache.resident_bytes += chunk_bytes::<T>(chunk.len());
+        cache.slots.push(Slot {
+            len: chunk.len(),
+            resident: Some(Arc::new(chunk)),
+            dirty: true,
+            spilled: None,
+            last_used: 0,
+        });
+        let chunk_index = cache.slots.len() - 1;
+        cache.touch(chunk_index);
+        self.enforce_budget(&mut cache)
+    }
+
+    /// Get a copy of the item at a particular index.
+    pub fn get(&self, index: usize) -> Result<T, FormatError> {
+        let (chunk_index, pos) = self.locate(index).expect("Index out of range");
+        Ok(self.load(chunk_index)?[pos].clone())
+    }
+
+    /// Replace the item at a particular index, returning the old one.
+    pub fn set(&mut self, index: usize, t: T) -> Result<T, FormatError> {
+        let (chunk_index, pos) = self.locate(index).expect("Index out of range");
+        self.modify(chunk_index, |chunk| mem::replace(&mut chunk[pos], t))
+    }
+
+    /// Run `f` over every chunk in parallel, streaming the chunks through the cache.
+    pub fn par_map_chunks<R, F>(&self, f: F) -> Result<Vec<R>, FormatError>
+    where R: Send, F: Fn(&[T]) -> R + Send + Sync, {
+        let map = |index| self.load(index).map(|chunk| f(&chunk));
+        #[cfg(feature = "rayon")]
+        let mapped = (0..self.chunk_amount()).into_par_iter().map(map).collect();
+        #[cfg(not(feature = "rayon"))]
+        let mapped = (0..self.chunk_amount()).map(map).collect();
+        mapped
+    }
+
+    /// Check if the list contains a given item, scanning the chunks in parallel.
+    pub fn contains(&self, t: &T) -> Result<bool, FormatError>
+    where T: PartialEq, {
+        let find = |index| match self.load(index) {
+            Ok(chunk) => chunk.contains(t).then_some(Ok(())),
+            Err(e) => Some(Err(e)),
+        };
+        #[cfg(feature = "rayon")]
+        let found = (0..self.chunk_amount()).into_par_iter().find_map_any(find);
+        #[cfg(not(feature = "rayon"))]
+        let found = (0..self.chunk_amount()).find_map(find);
+        found.transpose().map(|found| found.is_some())
+    }
+
+    /// Return a new Vec containing all elements (in order).
+    pub fn get_list(&self) -> Result<Vec<T>, FormatError> {
+        let mut list = Vec::with_capacity(self.len());
+        for index in 0..self.chunk_amount() {
+            list.extend_from_slice(&self.load(index)?);
+        }
+        Ok(list)
+    }
+
+    /// Copy every element into an in-memory ChunkList with the same layout.
+    pub fn to_chunk_list(&self) -> Result<ChunkList<T>, FormatError>
+    where T: Ord + Debug, {
+        let mut list = ChunkList::new(self.chunk_size);
+        for index in 0..self.chunk_amount() {
+            list.my_list.push(Arc::new(self.load(index)?.to_vec()));
+        }
+        Ok(list)
+    }
+
+    /// Return the total number of elements.
+    pub fn len(&self) -> usize {
+        lock(&self.cache).slots.iter().map(|slot| slot.len).sum()
+    }
+
+    /// Check if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get current chunk size
+    pub fn get_chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Get amount of chunks in the list
+    pub fn chunk_amount(&self) -> usize {
+        lock(&self.cache).slots.len()
+    }
+
+    /// Get the memory budget for resident chunks, in bytes
+    pub fn memory_budget(&self) -> usize {
+        self.memory_budget
+    }
+
+    /// Approximate bytes used by resident chunks (`size_of::<T>()` per element).
+    pub fn resident_bytes(&self) -> usize {
+        lock(&self.cache).resident_bytes
+    }
+
+    /// Number of chunks currently held in memory.
+    pub fn resident_chunks(&self) -> usize {
+        lock(&self.cache).lru.len()
+    }
+}
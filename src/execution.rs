@@ -0,0 +1,119 @@
+use rayon::ThreadPool;
+use std::sync::Arc;
+
+/// Lists with fewer elements than this run their operations sequentially by default.
+pub const DEFAULT_SEQUENTIAL_CUTOFF: usize = 4096;
+
+/// How a ChunkList runs its parallel operations.
+///
+/// By default operations run on rayon's global pool, except on lists smaller than
+/// `DEFAULT_SEQUENTIAL_CUTOFF`, where splitting the work costs more than it saves.
+#[derive(Debug, Clone)]
+pub struct ExecutionConfig {
+    pool: Option<Arc<ThreadPool>>,
+    sequential_cutoff: usize,
+    sequential: bool,
+    seed: Option<u64>,
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutionConfig {
+    /// Run on rayon's global pool, sequentially below `DEFAULT_SEQUENTIAL_CUTOFF` elements.
+    pub fn new() -> Self {
+        Self {
+            pool: None,
+            sequential_cutoff: DEFAULT_SEQUENTIAL_CUTOFF,
+            sequential: false,
+            seed: None,
+        }
+    }
+
+    /// Run everything on the calling thread.
+    pub fn sequential() -> Self {
+        Self {
+            sequential: true,
+            ..Self::new()
+        }
+    }
+
+    /// Run parallel operations on `pool` instead of rayon's global pool.
+    pub fn with_pool(mut self, pool: Arc<ThreadPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Run operations on lists with fewer than `cutoff` elements sequentially.
+    pub fn with_sequential_cutoff(mut self, cutoff: usize) -> Self {
+        self.sequential_cutoff = cutoff;
+        self
+    }
+
+    /// Deterministic mode: every operation gives the same result however its work gets
+    /// scheduled (`remove` takes the first occurrence, folds combine chunk results in
+    /// order, ...). `seed` is there for seeding the caller's own random number generators
+    /// (see `get_seed`). Replication leaders still pick a random epoch, so a restarted
+    /// leader is never mistaken for the old one.
+    pub fn with_deterministic(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The pool parallel operations run on, if not rayon's global pool.
+    pub fn pool(&self) -> Option<&Arc<ThreadPool>> {
+        self.pool.as_ref()
+    }
+
+    /// Get the sequential cutoff
+    pub fn get_sequential_cutoff(&self) -> usize {
+        self.sequential_cutoff
+    }
+
+    /// Whether everything runs on the calling thread.
+    pub fn is_sequential(&self) -> bool {
+        self.sequential
+    }
+
+    /// Whether deterministic mode is on.
+    pub fn is_deterministic(&self) -> bool {
+        self.seed.is_some()
+    }
+
+    /// Get the deterministic mode seed
+    pub fn get_seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// How many threads parallel operations can spread over.
+    pub(crate) fn threads(&self) -> usize {
+        match (&self.pool, self.sequential) {
+            (_, true) => 1,
+            (Some(pool), false) => pool.current_num_threads(),
+            (None, false) => rayon::current_num_threads(),
+        }
+    }
+
+    /// Whether an operation over `work` elements runs in parallel.
+    pub fn is_parallel(&self, work: usize) -> bool {
+        !self.sequential && work >= self.sequential_cutoff
+    }
+
+    /// Run `op` for an operation over `work` elements. In parallel, `op` runs on the
+    /// configured pool and gets 1 as the minimum number of items per rayon task;
+    /// sequentially, it runs on the calling thread and gets `usize::MAX`, so rayon
+    /// iterators built with `with_min_len` never split.
+    pub(crate) fn run<R, F>(&self, work: usize, op: F) -> R
+    where R: Send, F: FnOnce(usize) -> R + Send, {
+        if !self.is_parallel(work) {
+            return op(usize::MAX);
+        }
+        match &self.pool {
+            Some(pool) => pool.install(|| op(1)),
+            None => op(1),
+        }
+    }
+}
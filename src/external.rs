@@ -0,0 +1,278 @@
+use crate::disk::SpillFile;
+use crate::format::{crc32, decode_chunk, ChunkCodec, ChunkEntry, DefaultCodec, FileHeader, FormatError, PAYLOAD_ALIGN, VERSION};
+use crate::{ChunkList, DiskChunkList};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Sorts more elements than fit in memory.
+///
+/// The input is read in batches that fit under the memory limit; each batch is cut into
+/// chunk-sized runs that are sorted in parallel and spilled to a temporary file. The runs
+/// are then k-way merged, in several passes if there are too many to merge at once, into
+/// a ChunkList, a `DiskChunkList` or a file in the native format.
+///
+/// The memory limit is approximate: it counts `size_of::<T>()` per element, and is never
+/// less than two chunks.
+#[derive(Debug, Clone)]
+pub struct ExternalSort {
+    chunk_size: usize,
+    memory_limit: usize,
+    temp_dir: PathBuf,
+}
+
+/// A sorted run in the spill file, as a sequence of chunk-sized blocks.
+type Run = Vec<ChunkEntry>;
+
+/// Reads a run back one block at a time.
+struct RunCursor<T> {
+    blocks: std::vec::IntoIter<ChunkEntry>,
+    buffer: std::vec::IntoIter<T>,
+}
+
+impl<T> RunCursor<T> {
+    fn next<C: ChunkCodec<T>>(&mut self, file: &mut SpillFile, codec: &C) -> Result<Option<T>, FormatError> {
+        loop {
+            if let Some(item) = self.buffer.next() {
+                return Ok(Some(item));
+            }
+            let Some(entry) = self.blocks.next() else { return Ok(None) };
+            let payload = file.read(&entry)?;
+            self.buffer = decode_chunk(0, &entry, &payload, codec)?.into_iter();
+        }
+    }
+}
+
+fn encode<T, C: ChunkCodec<T>>(items: &[T], codec: &C) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for item in items {
+        codec.encode(item, &mut payload);
+    }
+    payload
+}
+
+/// Merge `runs` in order, passing every element to `emit`. Equal elements keep the order
+/// of their runs, so the merge is stable.
+fn merge<T, C>(
+    runs: &[Run],
+    file: &mut SpillFile,
+    codec: &C,
+    mut emit: impl FnMut(T) -> Result<(), FormatError>,
+) -> Result<(), FormatError>
+where T: Ord, C: ChunkCodec<T>, {
+    let mut cursors: Vec<RunCursor<T>> = runs
+        .iter()
+        .map(|run| RunCursor {
+            blocks: run.clone().into_iter(),
+            buffer: Vec::new().into_iter(),
+        })
+        .collect();
+    let mut heap = BinaryHeap::with_capacity(cursors.len());
+    for (index, cursor) in cursors.iter_mut().enumerate() {
+        if let Some(item) = cursor.next(file, codec)? {
+            heap.push(Reverse((item, index)));
+        }
+    }
+    while let Some(Reverse((item, index))) = heap.pop() {
+        emit(item)?;
+        if let Some(next) = cursors[index].next(file, codec)? {
+            heap.push(Reverse((next, index)));
+        }
+    }
+    Ok(())
+}
+
+impl ExternalSort {
+    /// Create a sorter producing chunks of `chunk_size`, using about `memory_limit` bytes
+    /// and the system temp directory.
+    pub fn new(chunk_size: usize, memory_limit: usize) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            memory_limit,
+            temp_dir: std::env::temp_dir(),
+        }
+    }
+
+    /// Spill runs to `dir` instead of the system temp directory.
+    pub fn with_temp_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.temp_dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Get the chunk size of the output
+    pub fn get_chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Get the approximate memory limit, in bytes
+    pub fn memory_limit(&self) -> usize {
+        self.memory_limit
+    }
+
+    /// Sort `items`, then pass the sorted elements to `emit` one chunk at a time.
+    fn run<T, I, C>(
+        &self,
+        items: I,
+        codec: &C,
+        mut emit: impl FnMut(Vec<T>) -> Result<(), FormatError>,
+    ) -> Result<(), FormatError>
+    where T: Ord + Send, I: IntoIterator<Item = T>, C: ChunkCodec<T> + Sync, {
+        let chunk_size = self.chunk_size;
+        let element_size = mem::size_of::<T>().max(1);
+        // Chunks that fit in memory at once, leaving half the limit for their encodings
+        let chunks_per_batch = (self.memory_limit / 2 / element_size / chunk_size).max(1);
+        // Runs merged at once: one decoded block per run
+        let fan_in = (self.memory_limit / element_size / chunk_size).max(2);
+
+        let mut file = SpillFile::create(&self.temp_dir)?;
+        let mut runs: Vec<Run> = Vec::new();
+        let mut items = items.into_iter();
+        loop {
+            let mut batch: Vec<T> = items.by_ref().take(chunks_per_batch * chunk_size).collect();
+            if batch.is_empty() {
+                break;
+            }
+            let sort_run = |run: &mut [T]| {
+                run.sort();
+                (encode(run, codec), run.len())
+            };
+            #[cfg(feature = "rayon")]
+            let payloads: Vec<(Vec<u8>, usize)> = batch.par_chunks_mut(chunk_size).map(sort_run).collect();
+            #[cfg(not(feature = "rayon"))]
+            let payloads: Vec<(Vec<u8>, usize)> = batch.chunks_mut(chunk_size).map(sort_run).collect();
+            drop(batch);
+            for (payload, count) in payloads {
+                runs.push(vec![file.write(None, &payload, count)?]);
+            }
+        }
+
+        // Merge groups of runs into longer runs until one pass can merge them all
+        while runs.len() > fan_in {
+            let mut next_file = SpillFile::create(&self.temp_dir)?;
+            let mut next_runs = Vec::with_capacity(runs.len().div_ceil(fan_in));
+            for group in runs.chunks(fan_in) {
+                let mut run = Vec::new();
+                let mut block = Vec::with_capacity(chunk_size);
+                merge(group, &mut file, codec, |item| {
+                    block.push(item);
+                    if block.len() == chunk_size {
+                        run.push(next_file.write(None, &encode(&block, codec), block.len())?);
+                        block.clear();
+                    }
+                    Ok(())
+                })?;
+                if !block.is_empty() {
+                    run.push(next_file.write(None, &encode(&block, codec), block.len())?);
+                }
+                next_runs.push(run);
+            }
+            file = next_file;
+            runs = next_runs;
+        }
+
+        let mut block = Vec::with_capacity(chunk_size);
+        merge(&runs, &mut file, codec, |item| {
+            block.push(item);
+            if block.len() == chunk_size {
+                emit(mem::replace(&mut block, Vec::with_capacity(chunk_size)))?;
+            }
+            Ok(())
+        })?;
+        if !block.is_empty() {
+            emit(block)?;
+        }
+        Ok(())
+    }
+
+    /// Sort `items` into an in-memory ChunkList with the given codec for the runs.
+    pub fn sort_with<T, I, C>(&self, items: I, codec: &C) -> Result<ChunkList<T>, FormatError>
+    where T: Ord + Debug + Send + Sync + Clone, I: IntoIterator<Item = T>, C: ChunkCodec<T> + Sync, {
+        let mut list = ChunkList::new(self.chunk_size);
+        self.run(items, codec, |chunk| {
+            list.my_list.push(Arc::new(chunk));
+            Ok(())
+        })?;
+        Ok(list)
+    }
+
+    /// Sort `items` into a `DiskChunkList` that keeps about `memory_budget` bytes resident.
+    pub fn sort_to_disk_list_with<T, I, C>(
+        &self,
+        items: I,
+        codec: C,
+        memory_budget: usize,
+    ) -> Result<DiskChunkList<T, C>, FormatError>
+    where T: Ord + Send + Sync + Clone, I: IntoIterator<Item = T>, C: ChunkCodec<T> + Clone + Sync, {
+        let mut list = DiskChunkList::with_codec(self.chunk_size, memory_budget, &self.temp_dir, codec.clone())?;
+        self.run(items, &codec, |chunk| list.push_chunk(chunk))?;
+        Ok(list)
+    }
+
+    /// Sort `items` into a file in the native format, without holding the result in memory.
+    pub fn sort_to_file_with<T, I, C>(&self, items: I, path: impl AsRef<Path>, codec: &C) -> Result<(), FormatError>
+    where T: Ord + Send, I: IntoIterator<Item = T>, C: ChunkCodec<T> + Sync, {
+        // The chunk directory comes first, so spill the payloads to a temporary file
+        // and copy them in behind the directory once it is complete
+        let mut directory = Vec::new();
+        let mut checksums = Vec::new();
+        let mut payloads = SpillFile::create(&self.temp_dir)?;
+        let mut element_count = 0u64;
+        self.run(items, codec, |chunk| {
+            // Pad every payload so the next one stays aligned
+            let mut payload = encode(&chunk, codec);
+            let length = payload.len() as u64;
+            checksums.push(crc32(&payload));
+            payload.resize(payload.len().next_multiple_of(PAYLOAD_ALIGN as usize), 0);
+            let entry = payloads.write(None, &payload, chunk.len())?;
+            directory.push(ChunkEntry { length, ..entry });
+            element_count += chunk.len() as u64;
+            Ok(())
+        })?;
+
+        // Payloads are aligned relative to the spill file, so shift them by an aligned amount
+        let start = FileHeader::encoded_len(directory.len(), true).div_ceil(PAYLOAD_ALIGN) * PAYLOAD_ALIGN;
+        for entry in &mut directory {
+            entry.offset += start;
+        }
+        let header = FileHeader {
+            version: VERSION,
+            element_width: codec.element_width().unwrap_or(0) as u32,
+            chunk_size: self.chunk_size as u64,
+            element_count,
+            directory,
+            checksums: Some(checksums),
+        };
+        let mut out = BufWriter::new(File::create(path)?);
+        header.write_to(&mut out)?;
+        let header_len = FileHeader::encoded_len(header.directory.len(), true);
+        out.write_all(&[0; PAYLOAD_ALIGN as usize][..(start - header_len) as usize])?;
+        payloads.copy_to(&mut out)?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Sort `items` into an in-memory ChunkList.
+    pub fn sort<T, I>(&self, items: I) -> Result<ChunkList<T>, FormatError>
+    where T: Ord + Debug + Send + Sync + Clone + DefaultCodec, I: IntoIterator<Item = T>, T::Codec: Sync, {
+        self.sort_with(items, &T::Codec::default())
+    }
+
+    /// Sort `items` into a `DiskChunkList` that keeps about `memory_budget` bytes resident.
+    pub fn sort_to_disk_list<T, I>(&self, items: I, memory_budget: usize) -> Result<DiskChunkList<T, T::Codec>, FormatError>
+    where T: Ord + Send + Sync + Clone + DefaultCodec, I: IntoIterator<Item = T>, T::Codec: Clone + Sync, {
+        self.sort_to_disk_list_with(items, T::Codec::default(), memory_budget)
+    }
+
+    /// Sort `items` into a file in the native format.
+    pub fn sort_to_file<T, I>(&self, items: I, path: impl AsRef<Path>) -> Result<(), FormatError>
+    where T: Ord + Send + DefaultCodec, I: IntoIterator<Item = T>, T::Codec: Sync, {
+        self.sort_to_file_with(items, path, &T::Codec::default())
+    }
+}
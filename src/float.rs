@@ -0,0 +1,83 @@
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, Display};
+use core::hash::{Hash, Hasher};
+
+macro_rules! total_float {
+    ($(#[$doc:meta])* $name:ident($float:ty)) => {
+        $(#[$doc])*
+        ///
+        /// Floats aren't `Ord`, so they can't go in a ChunkList directly. This wrapper
+        /// orders them with `total_cmp`: -NaN < -inf < ... < -0.0 < +0.0 < ... < +inf < NaN,
+        /// and two values are only equal if their bits are. Files saved with it are
+        /// ordinary `FloatCodec` files.
+        #[derive(Clone, Copy, Default)]
+        #[repr(transparent)]
+        pub struct $name(pub $float);
+
+        impl $name {
+            /// Get the wrapped float
+            pub fn get(self) -> $float {
+                self.0
+            }
+        }
+
+        impl From<$float> for $name {
+            fn from(value: $float) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for $float {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+
+        impl Hash for $name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.to_bits().hash(state);
+            }
+        }
+
+        impl Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Debug::fmt(&self.0, f)
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Display::fmt(&self.0, f)
+            }
+        }
+    };
+}
+
+total_float!(
+    /// An `f32` with a total order, for use as a ChunkList element.
+    TotalF32(f32)
+);
+total_float!(
+    /// An `f64` with a total order, for use as a ChunkList element.
+    TotalF64(f64)
+);
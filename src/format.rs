@@ -0,0 +1,786 @@
+//! Native binary file format for ChunkLists.
+//!
+//! All integers are little-endian. A file is laid out as:
+//!
+//! ```text
+//! header (40 bytes)
+//!   magic          [u8; 4]   b"CHKL"
+//!   version        u16       1
+//!   flags          u16       FLAG_CHECKSUMS, other bits reserved
+//!   element_width  u32       encoded bytes per element, 0 if variable
+//!   reserved       u32       0
+//!   chunk_size     u64
+//!   element_count  u64
+//!   chunk_count    u64
+//! chunk directory (chunk_count entries of 24 bytes)
+//!   offset         u64       absolute file offset of the chunk payload
+//!   length         u64       payload length in bytes
+//!   count          u64       number of elements in the chunk
+//! chunk checksums (only with FLAG_CHECKSUMS, chunk_count entries of 4 bytes)
+//!   crc32          u32       CRC-32 of the chunk payload
+//! chunk payloads
+//!   the chunk's elements, one after another, as written by the `ChunkCodec`
+//! ```
+//!
+//! The directory lets a reader seek straight to any chunk (see `ChunkFileReader`)
+//! without decoding the ones before it. Every payload starts at a multiple of
+//! `PAYLOAD_ALIGN` bytes (the gaps are zero padding), so fixed-width payloads can be
+//! used in place from a memory-mapped file.
+//!
+//! Files carry checksums unless written with `WriteOptions::with_checksums(false)`, and
+//! readers verify every chunk they decode against them. `read_checked_with` can skip or
+//! quarantine corrupt chunks instead of failing, and `ChunkFileReader::verify` reports
+//! them without decoding anything.
+
+use crate::{ChunkList, TotalF32, TotalF64};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::fmt::{self, Debug, Display};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// File magic, the first four bytes of every file.
+pub const MAGIC: [u8; 4] = *b"CHKL";
+/// Current format version.
+pub const VERSION: u16 = 1;
+
+/// Alignment of every payload's file offset.
+pub const PAYLOAD_ALIGN: u64 = 16;
+
+/// Header flag: the directory is followed by a CRC-32 of every chunk payload.
+pub const FLAG_CHECKSUMS: u16 = 1;
+
+const HEADER_LEN: u64 = 40;
+const DIRECTORY_ENTRY_LEN: u64 = 24;
+
+/// Error raised by a `ChunkCodec` that can't decode its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodecError(String);
+
+impl CodecError {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self(reason.into())
+    }
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+/// Error reading or writing the native format.
+#[derive(Debug)]
+pub enum FormatError {
+    Io(io::Error),
+    /// The file doesn't start with `MAGIC`.
+    BadMagic,
+    /// The file was written by a newer, unknown version of the format.
+    UnsupportedVersion(u16),
+    /// The header or chunk directory is inconsistent.
+    InvalidHeader(String),
+    /// A chunk's payload couldn't be decoded.
+    InvalidChunk { index: usize, reason: String },
+    /// A chunk's payload doesn't match its stored checksum.
+    ChecksumMismatch { index: usize, expected: u32, actual: u32 },
+    /// A chunk was requested that doesn't exist.
+    ChunkOutOfRange { index: usize, chunk_count: usize },
+    /// Checksums were asked to be verified, but the file was written without them.
+    NoChecksums,
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Io(e) => write!(f, "I/O error: {}", e),
+            FormatError::BadMagic => write!(f, "not a chunk list file (bad magic)"),
+            FormatError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            FormatError::InvalidHeader(reason) => write!(f, "invalid header: {}", reason),
+            FormatError::InvalidChunk { index, reason } => {
+                write!(f, "invalid chunk #{}: {}", index, reason)
+            }
+            FormatError::ChecksumMismatch { index, expected, actual } => {
+                write!(f, "chunk #{} checksum mismatch: expected {:08x}, got {:08x}", index, expected, actual)
+            }
+            FormatError::ChunkOutOfRange { index, chunk_count } => {
+                write!(f, "chunk #{} out of range for {} chunks", index, chunk_count)
+            }
+            FormatError::NoChecksums => write!(f, "file has no chunk checksums"),
+        }
+    }
+}
+
+impl FormatError {
+    /// The chunk an error is about, if any.
+    pub fn chunk_index(&self) -> Option<usize> {
+        match self {
+            FormatError::InvalidChunk { index, .. }
+            | FormatError::ChecksumMismatch { index, .. }
+            | FormatError::ChunkOutOfRange { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for FormatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FormatError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FormatError {
+    fn from(e: io::Error) -> Self {
+        FormatError::Io(e)
+    }
+}
+
+/// Defines how elements are encoded in chunk payloads.
+pub trait ChunkCodec<T> {
+    /// Encoded size of every element, if it is fixed.
+    fn element_width(&self) -> Option<usize> {
+        None
+    }
+
+    /// Append the encoding of `item` to `out`.
+    fn encode(&self, item: &T, out: &mut Vec<u8>);
+
+    /// Decode one element from the front of `input`, advancing it past the bytes read.
+    fn decode(&self, input: &mut &[u8]) -> Result<T, CodecError>;
+}
+
+/// The codec used by `save`/`load` when none is given.
+pub trait DefaultCodec: Sized {
+    type Codec: ChunkCodec<Self> + Default;
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { 0xEDB8_8320 ^ (crc >> 1) } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE) of `bytes`.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &b| CRC32_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8))
+}
+
+/// Take `n` bytes from the front of `input`.
+fn take<'a>(input: &mut &'a [u8], n: usize) -> Result<&'a [u8], CodecError> {
+    if input.len() < n {
+        return Err(CodecError::new("unexpected end of chunk"));
+    }
+    let (head, tail) = input.split_at(n);
+    *input = tail;
+    Ok(head)
+}
+
+/// Fixed-width little-endian integers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IntCodec;
+
+/// Fixed-width little-endian IEEE 754 floats, plain or wrapped in `TotalF32`/`TotalF64`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FloatCodec;
+
+/// UTF-8 strings, each prefixed with its byte length as a u64.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StringCodec;
+
+macro_rules! fixed_width_codec {
+    ($codec:ty => $($t:ty),*) => {$(
+        impl ChunkCodec<$t> for $codec {
+            fn element_width(&self) -> Option<usize> {
+                Some(std::mem::size_of::<$t>())
+            }
+
+            fn encode(&self, item: &$t, out: &mut Vec<u8>) {
+                out.extend_from_slice(&item.to_le_bytes());
+            }
+
+            fn decode(&self, input: &mut &[u8]) -> Result<$t, CodecError> {
+                let bytes = take(input, std::mem::size_of::<$t>())?;
+                Ok(<$t>::from_le_bytes(bytes.try_into().unwrap()))
+            }
+        }
+    )*};
+}
+
+fixed_width_codec!(IntCodec => i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+fixed_width_codec!(FloatCodec => f32, f64);
+
+macro_rules! default_codec {
+    ($codec:ty => $($t:ty),*) => {$(
+        impl DefaultCodec for $t {
+            type Codec = $codec;
+        }
+    )*};
+}
+
+default_codec!(IntCodec => i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+
+macro_rules! total_float_codec {
+    ($($t:ty => $float:ty),*) => {$(
+        impl ChunkCodec<$t> for FloatCodec {
+            fn element_width(&self) -> Option<usize> {
+                Some(std::mem::size_of::<$float>())
+            }
+
+            fn encode(&self, item: &$t, out: &mut Vec<u8>) {
+                <Self as ChunkCodec<$float>>::encode(self, &item.0, out);
+            }
+
+            fn decode(&self, input: &mut &[u8]) -> Result<$t, CodecError> {
+                <Self as ChunkCodec<$float>>::decode(self, input).map(<$t>::from)
+            }
+        }
+    )*};
+}
+
+// Plain floats aren't `Ord`, so lists of them are saved through the total-order wrappers
+total_float_codec!(TotalF32 => f32, TotalF64 => f64);
+default_codec!(FloatCodec => TotalF32, TotalF64);
+
+impl ChunkCodec<String> for StringCodec {
+    fn encode(&self, item: &String, out: &mut Vec<u8>) {
+        out.extend_from_slice(&(item.len() as u64).to_le_bytes());
+        out.extend_from_slice(item.as_bytes());
+    }
+
+    fn decode(&self, input: &mut &[u8]) -> Result<String, CodecError> {
+        let len = u64::from_le_bytes(take(input, 8)?.try_into().unwrap());
+        let len = usize::try_from(len).map_err(|_| CodecError::new("string too long"))?;
+        let bytes = take(input, len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|e| CodecError::new(e.to_string()))
+    }
+}
+
+impl DefaultCodec for String {
+    type Codec = StringCodec;
+}
+
+/// Location of one chunk's payload in a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkEntry {
+    pub offset: u64,
+    pub length: u64,
+    pub count: u64,
+}
+
+/// The decoded header and chunk directory of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHeader {
+    pub version: u16,
+    pub element_width: u32,
+    pub chunk_size: u64,
+    pub element_count: u64,
+    pub directory: Vec<ChunkEntry>,
+    /// CRC-32 of every chunk payload, if the file has them.
+    pub checksums: Option<Vec<u32>>,
+}
+
+fn read_u16(r: &mut impl Read) -> io::Result<u16> {
+    let mut buf = [0; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+impl FileHeader {
+    /// Byte length of the header, directory and checksums, i.e. where the first payload
+    /// can start.
+    pub(crate) fn encoded_len(chunk_count: usize, checksums: bool) -> u64 {
+        let checksum_len = if checksums { 4 } else { 0 };
+        HEADER_LEN + (DIRECTORY_ENTRY_LEN + checksum_len) * chunk_count as u64
+    }
+
+    pub(crate) fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        let flags = if self.checksums.is_some() { FLAG_CHECKSUMS } else { 0 };
+        w.write_all(&MAGIC)?;
+        w.write_all(&self.version.to_le_bytes())?;
+        w.write_all(&flags.to_le_bytes())?;
+        w.write_all(&self.element_width.to_le_bytes())?;
+        w.write_all(&0u32.to_le_bytes())?;
+        w.write_all(&self.chunk_size.to_le_bytes())?;
+        w.write_all(&self.element_count.to_le_bytes())?;
+        w.write_all(&(self.directory.len() as u64).to_le_bytes())?;
+        for entry in &self.directory {
+            w.write_all(&entry.offset.to_le_bytes())?;
+            w.write_all(&entry.length.to_le_bytes())?;
+            w.write_all(&entry.count.to_le_bytes())?;
+        }
+        for checksum in self.checksums.iter().flatten() {
+            w.write_all(&checksum.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Read and validate a header and directory.
+    pub fn read_from(r: &mut impl Read) -> Result<Self, FormatError> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(FormatError::BadMagic);
+        }
+        let version = read_u16(r)?;
+        if version == 0 || version > VERSION {
+            return Err(FormatError::UnsupportedVersion(version));
+        }
+        let flags = read_u16(r)?;
+        let element_width = read_u32(r)?;
+        let _reserved = read_u32(r)?;
+        let chunk_size = read_u64(r)?;
+        let element_count = read_u64(r)?;
+        let chunk_count = read_u64(r)?;
+
+        let mut directory = Vec::new();
+        for _ in 0..chunk_count {
+            directory.push(ChunkEntry {
+                offset: read_u64(r)?,
+                length: read_u64(r)?,
+                count: read_u64(r)?,
+            });
+        }
+        let total = directory.iter().try_fold(0u64, |total, entry| total.checked_add(entry.count));
+        if total != Some(element_count) {
+            return Err(FormatError::InvalidHeader(
+                "chunk counts don't add up to the element count".to_string(),
+            ));
+        }
+        let checksums = if flags & FLAG_CHECKSUMS != 0 {
+            let mut checksums = Vec::with_capacity(directory.len());
+            for _ in 0..chunk_count {
+                checksums.push(read_u32(r)?);
+            }
+            Some(checksums)
+        } else {
+            None
+        };
+        Ok(Self {
+            version,
+            element_width,
+            chunk_size,
+            element_count,
+            directory,
+            checksums,
+        })
+    }
+
+    /// Check a chunk's payload against its stored checksum, if there is one.
+    pub fn check_chunk(&self, index: usize, payload: &[u8]) -> Result<(), FormatError> {
+        match self.checksums.as_ref().map(|checksums| checksums[index]) {
+            Some(expected) => {
+                let actual = crc32(payload);
+                if actual != expected {
+                    return Err(FormatError::ChecksumMismatch { index, expected, actual });
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Options for writing a list in the native format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Store a CRC-32 of every chunk payload (`FLAG_CHECKSUMS`).
+    pub checksums: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WriteOptions {
+    /// The defaults: with checksums.
+    pub fn new() -> Self {
+        Self { checksums: true }
+    }
+
+    /// Set whether chunk checksums are stored.
+    pub fn with_checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+}
+
+/// How a reader handles corrupt chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Return the first error.
+    FailFast,
+    /// Leave corrupt chunks out of the list and report them.
+    Skip,
+    /// Like `Skip`, but keep the raw payload of every corrupt chunk in the report.
+    Quarantine,
+}
+
+/// A chunk that failed its checksum or couldn't be decoded.
+#[derive(Debug)]
+pub struct CorruptChunk {
+    pub index: usize,
+    pub error: FormatError,
+    /// The raw payload, in `LoadMode::Quarantine`.
+    pub payload: Option<Vec<u8>>,
+}
+
+/// The result of reading a file with `read_checked_with`.
+#[derive(Debug)]
+pub struct LoadReport<T> {
+    /// The list, without any corrupt chunks.
+    pub list: ChunkList<T>,
+    pub corrupt: Vec<CorruptChunk>,
+}
+
+/// Read a payload of `length` bytes, without trusting `length` for the allocation up front.
+fn read_payload(r: &mut impl Read, length: u64) -> Result<Vec<u8>, FormatError> {
+    let mut payload = Vec::new();
+    r.take(length).read_to_end(&mut payload)?;
+    if payload.len() as u64 != length {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(payload)
+}
+
+/// Decode a whole chunk payload.
+pub(crate) fn decode_chunk<T, C>(index: usize, entry: &ChunkEntry, payload: &[u8], codec: &C) -> Result<Vec<T>, FormatError>
+where C: ChunkCodec<T>, {
+    let invalid = |reason: String| FormatError::InvalidChunk { index, reason };
+    let mut input = payload;
+    let mut chunk = Vec::with_capacity((entry.count as usize).min(payload.len()));
+    for _ in 0..entry.count {
+        chunk.push(codec.decode(&mut input).map_err(|e| invalid(e.to_string()))?);
+    }
+    if !input.is_empty() {
+        return Err(invalid(format!("{} trailing bytes", input.len())));
+    }
+    Ok(chunk)
+}
+
+/// Random access to the chunks of a file, without reading the whole file.
+#[derive(Debug)]
+pub struct ChunkFileReader<R> {
+    header: FileHeader,
+    reader: R,
+    // Checksums computed on demand for files that don't store them
+    computed: Vec<Option<u32>>,
+}
+
+impl ChunkFileReader<BufReader<File>> {
+    /// Open a file and read its header and chunk directory.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FormatError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> ChunkFileReader<R> {
+    /// Read the header and chunk directory from the start of `reader`.
+    pub fn new(mut reader: R) -> Result<Self, FormatError> {
+        reader.seek(SeekFrom::Start(0))?;
+        let header = FileHeader::read_from(&mut reader)?;
+        let computed = vec![None; header.directory.len()];
+        Ok(Self {
+            header,
+            reader,
+            computed,
+        })
+    }
+
+    /// The file's header and chunk directory.
+    pub fn header(&self) -> &FileHeader {
+        &self.header
+    }
+
+    /// Number of chunks in the file.
+    pub fn chunk_amount(&self) -> usize {
+        self.header.directory.len()
+    }
+
+    /// Number of elements in the file.
+    pub fn len(&self) -> usize {
+        self.header.element_count as usize
+    }
+
+    /// Check if the file holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.header.element_count == 0
+    }
+
+    /// Read the raw payload of one chunk.
+    pub fn read_chunk_bytes(&mut self, index: usize) -> Result<Vec<u8>, FormatError> {
+        let chunk_count = self.chunk_amount();
+        let entry = *self
+            .header
+            .directory
+            .get(index)
+            .ok_or(FormatError::ChunkOutOfRange { index, chunk_count })?;
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        read_payload(&mut self.reader, entry.length)
+    }
+
+    /// Read, verify and decode one chunk.
+    pub fn read_chunk<T, C>(&mut self, index: usize, codec: &C) -> Result<Vec<T>, FormatError>
+    where C: ChunkCodec<T>, {
+        let payload = self.read_chunk_bytes(index)?;
+        self.header.check_chunk(index, &payload)?;
+        decode_chunk(index, &self.header.directory[index], &payload, codec)
+    }
+
+    /// The checksum of one chunk: the stored one, or computed from the payload (once)
+    /// if the file has none.
+    pub fn chunk_checksum(&mut self, index: usize) -> Result<u32, FormatError> {
+        if let Some(checksums) = &self.header.checksums {
+            if let Some(&checksum) = checksums.get(index) {
+                return Ok(checksum);
+            }
+        }
+        if let Some(Some(checksum)) = self.computed.get(index) {
+            return Ok(*checksum);
+        }
+        let checksum = crc32(&self.read_chunk_bytes(index)?);
+        self.computed[index] = Some(checksum);
+        Ok(checksum)
+    }
+
+    /// Whether the file stores chunk checksums.
+    pub fn has_checksums(&self) -> bool {
+        self.header.checksums.is_some()
+    }
+
+    /// Check every chunk against its stored checksum and report the corrupt ones.
+    ///
+    /// Files without checksums have nothing to check against and fail with
+    /// `FormatError::NoChecksums`; use `verify_with` to at least check that every chunk decodes.
+    pub fn verify(&mut self) -> Result<Vec<CorruptChunk>, FormatError> {
+        if !self.has_checksums() {
+            return Err(FormatError::NoChecksums);
+        }
+        self.verify_chunks(|_, _| Ok(()))
+    }
+
+    /// Check every chunk against its stored checksum and that it decodes with `codec`,
+    /// and report the corrupt ones.
+    pub fn verify_with<T, C>(&mut self, codec: &C) -> Result<Vec<CorruptChunk>, FormatError>
+    where C: ChunkCodec<T>, {
+        let directory = self.header.directory.clone();
+        self.verify_chunks(|index, payload| decode_chunk::<T, C>(index, &directory[index], payload, codec).map(drop))
+    }
+
+    fn verify_chunks(
+        &mut self,
+        check: impl Fn(usize, &[u8]) -> Result<(), FormatError>,
+    ) -> Result<Vec<CorruptChunk>, FormatError> {
+        let mut corrupt = Vec::new();
+        for index in 0..self.chunk_amount() {
+            let payload = self.read_chunk_bytes(index)?;
+            if let Err(error) = self.header.check_chunk(index, &payload).and_then(|_| check(index, &payload)) {
+                corrupt.push(CorruptChunk {
+                    index,
+                    error,
+                    payload: None,
+                });
+            }
+        }
+        Ok(corrupt)
+    }
+}
+
+impl<T> ChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Write the list in the native format with the given codec.
+    pub fn write_with<W, C>(&self, w: W, codec: &C) -> Result<(), FormatError>
+    where W: Write, C: ChunkCodec<T> + Sync, {
+        self.write_with_options(w, codec, WriteOptions::default())
+    }
+
+    /// Write the list in the native format with the given codec and options.
+    pub fn write_with_options<W, C>(&self, mut w: W, codec: &C, options: WriteOptions) -> Result<(), FormatError>
+    where W: Write, C: ChunkCodec<T> + Sync, {
+        // Encode chunks in parallel; the directory needs every payload length up front
+        let encode = |chunk: &Arc<Vec<T>>| {
+            let mut payload = Vec::with_capacity(chunk.len() * codec.element_width().unwrap_or(8));
+            for item in chunk.iter() {
+                codec.encode(item, &mut payload);
+            }
+            let checksum = if options.checksums { crc32(&payload) } else { 0 };
+            (payload, checksum)
+        };
+        #[cfg(feature = "rayon")]
+        let payloads: Vec<(Vec<u8>, u32)> = self.my_list.par_iter().map(encode).collect();
+        #[cfg(not(feature = "rayon"))]
+        let payloads: Vec<(Vec<u8>, u32)> = self.my_list.iter().map(encode).collect();
+
+        let align = |offset: u64| offset.div_ceil(PAYLOAD_ALIGN) * PAYLOAD_ALIGN;
+        let mut offset = FileHeader::encoded_len(payloads.len(), options.checksums);
+        let mut directory = Vec::with_capacity(payloads.len());
+        for (chunk, (payload, _)) in self.my_list.iter().zip(&payloads) {
+            offset = align(offset);
+            directory.push(ChunkEntry {
+                offset,
+                length: payload.len() as u64,
+                count: chunk.len() as u64,
+            });
+            offset += payload.len() as u64;
+        }
+        let header = FileHeader {
+            version: VERSION,
+            element_width: codec.element_width().unwrap_or(0) as u32,
+            chunk_size: self.chunk_size as u64,
+            element_count: self.len() as u64,
+            directory,
+            checksums: options
+                .checksums
+                .then(|| payloads.iter().map(|(_, checksum)| *checksum).collect()),
+        };
+        header.write_to(&mut w)?;
+        let mut position = FileHeader::encoded_len(payloads.len(), options.checksums);
+        for (entry, (payload, _)) in header.directory.iter().zip(&payloads) {
+            w.write_all(&[0; PAYLOAD_ALIGN as usize][..(entry.offset - position) as usize])?;
+            w.write_all(payload)?;
+            position = entry.offset + entry.length;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Read a list in the native format with the given codec, keeping its chunk layout.
+    pub fn read_with<R, C>(r: R, codec: &C) -> Result<Self, FormatError>
+    where R: Read, C: ChunkCodec<T>, {
+        Self::read_checked_with(r, codec, LoadMode::FailFast).map(|report| report.list)
+    }
+
+    /// Read a list in the native format with the given codec, handling corrupt chunks
+    /// according to `mode`. I/O errors and a bad header always fail.
+    pub fn read_checked_with<R, C>(mut r: R, codec: &C, mode: LoadMode) -> Result<LoadReport<T>, FormatError>
+    where R: Read, C: ChunkCodec<T>, {
+        let header = FileHeader::read_from(&mut r)?;
+        let mut position = FileHeader::encoded_len(header.directory.len(), header.checksums.is_some());
+        let mut list = ChunkList::new(header.chunk_size as usize);
+        let mut corrupt = Vec::new();
+        for (index, entry) in header.directory.iter().enumerate() {
+            // Payloads are read in order, so a plain `Read` (no seeking) is enough
+            if entry.offset < position {
+                return Err(FormatError::InvalidHeader(format!("chunk #{} overlaps the previous one", index)));
+            }
+            io::copy(&mut r.by_ref().take(entry.offset - position), &mut io::sink())?;
+            let payload = read_payload(&mut r, entry.length)?;
+            position = entry.offset + entry.length;
+            let chunk = header
+                .check_chunk(index, &payload)
+                .and_then(|_| decode_chunk(index, entry, &payload, codec));
+            match (chunk, mode) {
+                (Ok(chunk), _) => list.my_list.push(Arc::new(chunk)),
+                (Err(error), LoadMode::FailFast) => return Err(error),
+                (Err(error), LoadMode::Skip) => corrupt.push(CorruptChunk {
+                    index,
+                    error,
+                    payload: None,
+                }),
+                (Err(error), LoadMode::Quarantine) => corrupt.push(CorruptChunk {
+                    index,
+                    error,
+                    payload: Some(payload),
+                }),
+            }
+        }
+        Ok(LoadReport { list, corrupt })
+    }
+
+    /// Save the list to a file with the given codec.
+    pub fn save_with<C>(&self, path: impl AsRef<Path>, codec: &C) -> Result<(), FormatError>
+    where C: ChunkCodec<T> + Sync, {
+        self.write_with(BufWriter::new(File::create(path)?), codec)
+    }
+
+    /// Load a list from a file with the given codec.
+    pub fn load_with<C>(path: impl AsRef<Path>, codec: &C) -> Result<Self, FormatError>
+    where C: ChunkCodec<T>, {
+        Self::read_with(BufReader::new(File::open(path)?), codec)
+    }
+
+    /// Load a list from a file with the given codec, handling corrupt chunks according to `mode`.
+    pub fn load_checked_with<C>(path: impl AsRef<Path>, codec: &C, mode: LoadMode) -> Result<LoadReport<T>, FormatError>
+    where C: ChunkCodec<T>, {
+        Self::read_checked_with(BufReader::new(File::open(path)?), codec, mode)
+    }
+
+    /// The checksum every chunk would have in a file written with `codec`, computed now.
+    pub fn checksums_with<C>(&self, codec: &C) -> Vec<u32>
+    where C: ChunkCodec<T> + Sync, {
+        let checksum = |chunk: &Arc<Vec<T>>| {
+            let mut payload = Vec::new();
+            for item in chunk.iter() {
+                codec.encode(item, &mut payload);
+            }
+            crc32(&payload)
+        };
+        #[cfg(feature = "rayon")]
+        let checksums = self.my_list.par_iter().map(checksum).collect();
+        #[cfg(not(feature = "rayon"))]
+        let checksums = self.my_list.iter().map(checksum).collect();
+        checksums
+    }
+}
+
+impl<T> ChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone + DefaultCodec, T::Codec: Sync, {
+    /// Write the list in the native format.
+    pub fn write_to(&self, w: impl Write) -> Result<(), FormatError> {
+        self.write_with(w, &T::Codec::default())
+    }
+
+    /// Read a list in the native format.
+    pub fn read_from(r: impl Read) -> Result<Self, FormatError> {
+        Self::read_with(r, &T::Codec::default())
+    }
+
+    /// Save the list to a file in the native format.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), FormatError> {
+        self.save_with(path, &T::Codec::default())
+    }
+
+    /// Load a list from a file in the native format.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, FormatError> {
+        Self::load_with(path, &T::Codec::default())
+    }
+
+    /// Load a list from a file in the native format, handling corrupt chunks according to `mode`.
+    pub fn load_checked(path: impl AsRef<Path>, mode: LoadMode) -> Result<LoadReport<T>, FormatError> {
+        Self::load_checked_with(path, &T::Codec::default(), mode)
+    }
+
+    /// The checksum every chunk would have in a saved file, computed now.
+    pub fn checksums(&self) -> Vec<u32> {
+        self.checksums_with(&T::Codec::default())
+    }
+}
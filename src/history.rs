@@ -0,0 +1,473 @@
+use crate::{ChunkEvent, ChunkList};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::fmt::Debug;
+use std::mem;
+use std::sync::Arc;
+
+/// A ChunkList that records how to revert every mutation, for undo/redo.
+///
+/// Each recorded operation remembers exactly where it touched the chunk layout, so
+/// undoing restores the same layout, not just the same elements. Operations can be
+/// grouped under a named checkpoint and undone together. The history is bounded by an
+/// approximate memory limit; the oldest groups are forgotten first.
+#[derive(Debug)]
+pub struct HistoryChunkList<T> {
+    list: ChunkList<T>,
+    undo_stack: Vec<Group<T>>,
+    redo_stack: Vec<Group<T>>,
+    group_open: bool,
+    memory_limit: usize,
+    memory_used: usize,
+}
+
+/// A named (checkpoint) or anonymous (single operation) unit of undo.
+#[derive(Debug)]
+struct Group<T> {
+    name: Option<String>,
+    ops: Vec<Op<T>>,
+    bytes: usize,
+}
+
+/// Sort permutation: `sorted[k] = original[permutation[k]]`, stored as u32 when it fits.
+#[derive(Debug)]
+enum Permutation {
+    Small(Vec<u32>),
+    Large(Vec<usize>),
+}
+
+impl Permutation {
+    fn new(indices: Vec<usize>) -> Self {
+        if indices.len() <= u32::MAX as usize {
+            Permutation::Small(indices.into_iter().map(|i| i as u32).collect())
+        } else {
+            Permutation::Large(indices)
+        }
+    }
+
+    fn get(&self, k: usize) -> usize {
+        match self {
+            Permutation::Small(p) => p[k] as usize,
+            Permutation::Large(p) => p[k],
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Permutation::Small(p) => p.len(),
+            Permutation::Large(p) => p.len(),
+        }
+    }
+
+    fn bytes(&self) -> usize {
+        match self {
+            Permutation::Small(p) => p.len() * mem::size_of::<u32>(),
+            Permutation::Large(p) => p.len() * mem::size_of::<usize>(),
+        }
+    }
+}
+
+/// A recorded operation. Values are held here while they are not in the list
+/// (i.e. after undoing an add, or after applying a removal).
+///
+/// Operations are (re-)applied through the list's own mutators where those produce the
+/// recorded layout, and reverted by hand otherwise; either way observers of the list see
+/// every change.
+#[derive(Debug)]
+enum Op<T> {
+    Add { chunk: usize, new_chunk: bool, value: Option<T> },
+    Set { index: usize, value: T },
+    Insert { index: usize, chunk: usize, pos: usize, new_chunk: bool, split: bool, value: Option<T> },
+    RemoveAt { index: usize, chunk: usize, pos: usize, value: Option<T> },
+    RemoveAll { value: T, removed: Vec<(usize, Vec<usize>)> },
+    Sort { chunk_lengths: Vec<usize>, permutation: Permutation },
+}
+
+impl<T> Op<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Approximate heap + inline size of this record.
+    fn bytes(&self) -> usize {
+        let extra = match self {
+            Op::RemoveAll { removed, .. } => removed
+                .iter()
+                .map(|(_, positions)| (positions.len() + 1) * mem::size_of::<usize>())
+                .sum(),
+            Op::Sort { chunk_lengths, permutation } => {
+                chunk_lengths.len() * mem::size_of::<usize>() + permutation.bytes()
+            }
+            _ => 0,
+        };
+        mem::size_of::<Self>() + extra
+    }
+
+    /// (Re-)apply the operation to the list.
+    fn forward(&mut self, list: &mut ChunkList<T>) {
+        match self {
+            Op::Add { value, .. } => list.add(value.take().unwrap()),
+            Op::Set { .. } => self.swap(list),
+            Op::Insert { index, value, .. } => list.insert(*index, value.take().unwrap()),
+            Op::RemoveAt { index, value, .. } => *value = Some(list.remove_at(*index)),
+            Op::RemoveAll { value, removed } => {
+                let retain = |chunk: &mut Arc<Vec<T>>| ChunkList::remove_from_chunk(chunk, value, true);
+                #[cfg(feature = "rayon")]
+                let chunk_removed: Vec<Vec<(usize, T)>> = list.execution.run(list.len(), |min_len| {
+                    list.my_list.par_iter_mut().with_min_len(min_len).map(retain).collect()
+                });
+                #[cfg(not(feature = "rayon"))]
+                let chunk_removed: Vec<Vec<(usize, T)>> = list.my_list.iter_mut().map(retain).collect();
+                *removed = chunk_removed
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, chunk_removed)| !chunk_removed.is_empty())
+                    .map(|(chunk, chunk_removed)| (chunk, chunk_removed.iter().map(|(pos, _)| *pos).collect()))
+                    .collect();
+                list.report_removed(chunk_removed);
+            }
+            Op::Sort { permutation, .. } => {
+                let mut items: Vec<Option<T>> = take_items(&mut list.my_list).into_iter().map(Some).collect();
+                let sorted = (0..permutation.len())
+                    .map(|k| items[permutation.get(k)].take().unwrap())
+                    .collect();
+                // Same layout and event as `ChunkList::sort`
+                list.refill(sorted);
+                list.notify(ChunkEvent::Sorted);
+            }
+        }
+    }
+
+    /// Revert the operation.
+    fn backward(&mut self, list: &mut ChunkList<T>) {
+        match self {
+            Op::Add { chunk, new_chunk, value } => {
+                let removed = if *new_chunk {
+                    Arc::unwrap_or_clone(list.my_list.pop().unwrap()).pop().unwrap()
+                } else {
+                    Arc::make_mut(&mut list.my_list[*chunk]).pop().unwrap()
+                };
+                let index = list.chunk_offset(*chunk) + list.my_list.get(*chunk).map_or(0, |c| c.len());
+                list.notify(ChunkEvent::Removed { index, value: removed.clone() });
+                *value = Some(removed);
+            }
+            Op::Set { .. } => self.swap(list),
+            Op::Insert { index, chunk, pos, new_chunk, split, value } => {
+                let chunks = &mut list.my_list;
+                if *split {
+                    let tail = Arc::unwrap_or_clone(chunks.remove(*chunk + 1));
+                    Arc::make_mut(&mut chunks[*chunk]).extend(tail);
+                }
+                let removed = Arc::make_mut(&mut chunks[*chunk]).remove(*pos);
+                if *new_chunk {
+                    chunks.pop();
+                }
+                list.notify(ChunkEvent::Removed { index: *index, value: removed.clone() });
+                *value = Some(removed);
+            }
+            Op::RemoveAt { index, chunk, pos, value } => {
+                let restored = value.take().unwrap();
+                Arc::make_mut(&mut list.my_list[*chunk]).insert(*pos, restored.clone());
+                list.notify(ChunkEvent::Inserted { index: *index, value: restored });
+            }
+            Op::RemoveAll { value, removed } => {
+                let report = list.is_observed();
+                let mut events = Vec::new();
+                for (chunk, positions) in removed.iter() {
+                    let target = Arc::make_mut(&mut list.my_list[*chunk]);
+                    for &pos in positions {
+                        target.insert(pos, value.clone());
+                    }
+                    if report {
+                        let offset = list.chunk_offset(*chunk);
+                        for &pos in positions {
+                            events.push(ChunkEvent::Inserted { index: offset + pos, value: value.clone() });
+                        }
+                    }
+                }
+                list.notify_all(events);
+            }
+            Op::Sort { chunk_lengths, permutation } => {
+                // Observers are told about every chunk that changes, so keep the sorted ones
+                let sorted = match list.is_observed() {
+                    true => list.get_list(),
+                    false => take_items(&mut list.my_list),
+                };
+                let mut items: Vec<Option<T>> = vec![None; permutation.len()];
+                for (k, item) in sorted.into_iter().enumerate() {
+                    items[permutation.get(k)] = Some(item);
+                }
+                let mut original = items.into_iter().map(Option::unwrap);
+                let chunks = chunk_lengths
+                    .iter()
+                    .map(|&len| Arc::new(original.by_ref().take(len).collect()))
+                    .collect();
+                list.replace_chunks(chunks);
+            }
+        }
+    }
+
+    /// Helper: Swap a recorded `Set` value with the one in the list.
+    fn swap(&mut self, list: &mut ChunkList<T>) {
+        if let Op::Set { index, value } = self {
+            let current = list.get(*index).clone();
+            list.set(*index, mem::replace(value, current));
+        }
+    }
+}
+
+/// Move every element out of the chunks, in order.
+fn take_items<T: Clone>(chunks: &mut Vec<Arc<Vec<T>>>) -> Vec<T> {
+    mem::take(chunks)
+        .into_iter()
+        .flat_map(Arc::unwrap_or_clone)
+        .collect()
+}
+
+impl<T> HistoryChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Creates a new, empty HistoryChunkList with the specified chunk size and no memory limit.
+    pub fn new(chunk_size: usize) -> Self {
+        Self::from(ChunkList::new(chunk_size))
+    }
+
+    /// Limit the approximate memory used by recorded history, in bytes.
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = bytes;
+        self.enforce_limit();
+        self
+    }
+
+    /// Read access to the underlying list.
+    pub fn list(&self) -> &ChunkList<T> {
+        &self.list
+    }
+
+    /// Unwrap the underlying list, dropping the history.
+    pub fn into_inner(self) -> ChunkList<T> {
+        self.list
+    }
+
+    /// Approximate memory currently used by recorded history, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_used
+    }
+
+    /// Apply a new operation and record it.
+    fn record(&mut self, mut op: Op<T>) {
+        op.forward(&mut self.list);
+        let bytes = op.bytes();
+
+        // A new operation invalidates everything that could be redone
+        for group in self.redo_stack.drain(..) {
+            self.memory_used -= group.bytes;
+        }
+        match self.undo_stack.last_mut() {
+            Some(group) if self.group_open => {
+                group.ops.push(op);
+                group.bytes += bytes;
+            }
+            _ => self.undo_stack.push(Group {
+                name: None,
+                ops: vec![op],
+                bytes,
+            }),
+        }
+        self.memory_used += bytes;
+        self.enforce_limit();
+    }
+
+    /// Forget the oldest history until we fit in the memory limit again.
+    /// An open checkpoint is never dropped while it is still collecting operations.
+    fn enforce_limit(&mut self) {
+        while self.memory_used > self.memory_limit {
+            let keep = if self.group_open { 1 } else { 0 };
+            if self.undo_stack.len() > keep {
+                let group = self.undo_stack.remove(0);
+                self.memory_used -= group.bytes;
+            } else if !self.redo_stack.is_empty() {
+                let group = self.redo_stack.remove(0);
+                self.memory_used -= group.bytes;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Start a named checkpoint: every operation until the next checkpoint (or
+    /// `end_checkpoint`, `undo`, `redo`) is undone and redone as one unit.
+    pub fn checkpoint(&mut self, name: &str) {
+        self.end_checkpoint();
+        self.redo_stack.drain(..).for_each(|group| self.memory_used -= group.bytes);
+        self.undo_stack.push(Group {
+            name: Some(name.to_string()),
+            ops: Vec::new(),
+            bytes: 0,
+        });
+        self.group_open = true;
+    }
+
+    /// Close the current checkpoint; later operations are recorded one by one again.
+    pub fn end_checkpoint(&mut self) {
+        if self.group_open {
+            self.group_open = false;
+            // Drop checkpoints nothing was recorded under
+            if self.undo_stack.last().is_some_and(|group| group.ops.is_empty()) {
+                self.undo_stack.pop();
+            }
+        }
+    }
+
+    /// Undo the most recent operation or checkpoint. Returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        self.end_checkpoint();
+        match self.undo_stack.pop() {
+            Some(mut group) => {
+                for op in group.ops.iter_mut().rev() {
+                    op.backward(&mut self.list);
+                }
+                self.redo_stack.push(group);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redo the most recently undone operation or checkpoint. Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        self.end_checkpoint();
+        match self.redo_stack.pop() {
+            Some(mut group) => {
+                for op in group.ops.iter_mut() {
+                    op.forward(&mut self.list);
+                }
+                self.undo_stack.push(group);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check if there is anything to undo.
+    pub fn can_undo(&self) -> bool {
+        self.undo_stack.iter().any(|group| !group.ops.is_empty())
+    }
+
+    /// Check if there is anything to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Name of the checkpoint `undo` would revert, if it is a named one.
+    pub fn undo_name(&self) -> Option<&str> {
+        self.undo_stack.last().and_then(|group| group.name.as_deref())
+    }
+
+    /// Name of the checkpoint `redo` would re-apply, if it is a named one.
+    pub fn redo_name(&self) -> Option<&str> {
+        self.redo_stack.last().and_then(|group| group.name.as_deref())
+    }
+
+    /// Forget all recorded history, keeping the list as it is.
+    pub fn clear_history(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.group_open = false;
+        self.memory_used = 0;
+    }
+
+    /// Add an element to the list (see `ChunkList::add`).
+    pub fn add(&mut self, t: T) {
+        let chunk_size = self.list.chunk_size;
+        let op = match self.list.my_list.iter().position(|chunk| chunk.len() < chunk_size) {
+            Some(chunk) => Op::Add { chunk, new_chunk: false, value: Some(t) },
+            None => Op::Add {
+                chunk: self.list.my_list.len(),
+                new_chunk: true,
+                value: Some(t),
+            },
+        };
+        self.record(op);
+    }
+
+    /// Set an item at a particular index.
+    pub fn set(&mut self, index: usize, t: T) {
+        if index >= self.list.len() {
+            panic!("Index out of range");
+        }
+        self.record(Op::Set { index, value: t });
+    }
+
+    /// Insert an element at a global index (see `ChunkList::insert`).
+    pub fn insert(&mut self, index: usize, t: T) {
+        let len = self.list.len();
+        if index > len {
+            panic!("Index out of range");
+        }
+        let chunks = &self.list.my_list;
+        let (chunk, pos, new_chunk) = if index == len {
+            match chunks.len() {
+                0 => (0, 0, true),
+                n => (n - 1, chunks[n - 1].len(), false),
+            }
+        } else {
+            let (chunk, pos) = self.list.locate(index).unwrap();
+            (chunk, pos, false)
+        };
+        let current = if new_chunk { 0 } else { chunks[chunk].len() };
+        let split = current + 1 > self.list.chunk_size.max(1);
+        self.record(Op::Insert { index, chunk, pos, new_chunk, split, value: Some(t) });
+    }
+
+    /// Remove and return the element at a global index.
+    pub fn remove_at(&mut self, index: usize) -> T {
+        let (chunk, pos) = match self.list.locate(index) {
+            Some(found) => found,
+            None => panic!("Index out of range"),
+        };
+        // The record keeps the removed value for undo, so the caller gets a copy
+        let value = self.list.my_list[chunk][pos].clone();
+        self.record(Op::RemoveAt { index, chunk, pos, value: None });
+        value
+    }
+
+    /// Remove all instances of `t`. Only the positions of removed elements are recorded.
+    pub fn remove_all(&mut self, t: &T) {
+        if self.list.contains(t) {
+            self.record(Op::RemoveAll { value: t.clone(), removed: Vec::new() });
+        }
+    }
+
+    /// Sort the entire list, recording the permutation instead of a copy of the elements.
+    pub fn sort(&mut self) {
+        let chunk_lengths: Vec<usize> = self.list.my_list.iter().map(|chunk| chunk.len()).collect();
+        let items = self.list.get_list();
+        let mut indices: Vec<usize> = (0..items.len()).collect();
+        // Stable, like the sorts in `ChunkList::sort`
+        #[cfg(feature = "rayon")]
+        if self.list.execution.is_parallel(items.len()) {
+            self.list.execution.run(items.len(), |_| indices.par_sort_by(|&a, &b| items[a].cmp(&items[b])));
+        } else {
+            indices.sort_by(|&a, &b| items[a].cmp(&items[b]));
+        }
+        #[cfg(not(feature = "rayon"))]
+        indices.sort_by(|&a, &b| items[a].cmp(&items[b]));
+        drop(items);
+        self.record(Op::Sort {
+            chunk_lengths,
+            permutation: Permutation::new(indices),
+        });
+    }
+}
+
+impl<T> From<ChunkList<T>> for HistoryChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Start recording history on an existing list.
+    fn from(list: ChunkList<T>) -> Self {
+        Self {
+            list,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            group_open: false,
+            memory_limit: usize::MAX,
+            memory_used: 0,
+        }
+    }
+}
@@ -1,2 +1,81 @@
-pub mod chunklist;
-pub use chunklist::ChunkList;
\ No newline at end of file
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
+#[cfg(feature = "rayon")]
+pub mod cancel;
+pub mod chunklist;
+#[cfg(feature = "std")]
+pub mod concurrent;
+#[cfg(feature = "std")]
+pub mod disk;
+#[cfg(feature = "rayon")]
+pub mod execution;
+#[cfg(feature = "std")]
+pub mod external;
+pub mod float;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+pub mod history;
+#[cfg(feature = "mmap")]
+pub mod mapped;
+#[cfg(feature = "std")]
+pub mod merkle;
+pub mod observer;
+#[cfg(feature = "rayon")]
+pub mod par;
+pub mod persistent;
+#[cfg(feature = "std")]
+pub mod queue;
+#[cfg(feature = "std")]
+pub mod replication;
+#[cfg(feature = "rayon")]
+pub mod scan;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "std")]
+pub mod sharded;
+pub mod snapshot;
+#[cfg(feature = "std")]
+mod sync;
+#[cfg(feature = "std")]
+pub mod text;
+#[cfg(feature = "std")]
+pub mod wal;
+#[cfg(feature = "rayon")]
+pub use cancel::{CancellationToken, Cancelled, Progress};
+pub use chunklist::ChunkList;
+#[cfg(feature = "std")]
+pub use concurrent::{ConcurrentChunkList, Transaction, TransactionError, TransactionRead};
+#[cfg(feature = "std")]
+pub use disk::DiskChunkList;
+#[cfg(feature = "rayon")]
+pub use execution::ExecutionConfig;
+#[cfg(feature = "std")]
+pub use external::ExternalSort;
+pub use float::{TotalF32, TotalF64};
+#[cfg(feature = "std")]
+pub use format::{ChunkCodec, ChunkFileReader, DefaultCodec, FormatError, LoadMode, WriteOptions};
+#[cfg(feature = "std")]
+pub use history::HistoryChunkList;
+#[cfg(feature = "mmap")]
+pub use mapped::{MappedChunkList, Pod};
+#[cfg(feature = "std")]
+pub use merkle::{ChunkPatch, MerkleTree, PatchError};
+pub use observer::{ChunkEvent, SubscriptionId};
+#[cfg(feature = "rayon")]
+pub use par::{IntoParIter, ParIter, ParIterMut};
+pub use persistent::PersistentChunkList;
+#[cfg(feature = "std")]
+pub use queue::{ChunkQueue, PopError, QueueClosed};
+#[cfg(feature = "std")]
+pub use replication::{ReplicationFollower, ReplicationLeader};
+#[cfg(feature = "std")]
+pub use sharded::ShardedChunkList;
+pub use snapshot::ChunkListSnapshot;
+#[cfg(feature = "std")]
+pub use text::TextError;
+#[cfg(feature = "std")]
+pub use wal::DurableChunkList;
+#[cfg(feature = "serde")]
+pub use serde_impl::layout as serde_layout;
\ No newline at end of file
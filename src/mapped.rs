@@ -0,0 +1,168 @@
+use crate::format::{FileHeader, FormatError};
+use crate::{ChunkList, TotalF32, TotalF64};
+use memmap2::Mmap;
+use rayon::prelude::*;
+use std::fmt::Debug;
+use std::fs::File;
+use std::marker::PhantomData;
+use std::mem;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Plain-old-data element types that can be used straight from a mapped file.
+///
+/// # Safety
+/// Implementors must be `Copy`, have no padding, and be valid for every bit pattern,
+/// and their native-endian in-memory layout must match what `IntCodec`/`FloatCodec`
+/// write on this platform.
+pub unsafe trait Pod: Copy + Send + Sync + 'static {}
+
+macro_rules! impl_pod {
+    ($($t:ty),*) => {$(unsafe impl Pod for $t {})*};
+}
+
+impl_pod!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, f32, f64, TotalF32, TotalF64);
+
+/// A read-only ChunkList that reads its elements straight out of a memory-mapped file
+/// in the native format, without decoding anything.
+///
+/// The file must have been written with a fixed-width codec whose encoding matches
+/// `T`'s in-memory layout (`IntCodec`/`FloatCodec` on a little-endian machine).
+#[derive(Debug)]
+pub struct MappedChunkList<T> {
+    mmap: Mmap,
+    header: FileHeader,
+    // Global index of the first element of every chunk
+    starts: Vec<usize>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> MappedChunkList<T> {
+    /// Map a file and validate that every chunk can be viewed as a `[T]` in place.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FormatError> {
+        let file = File::open(path)?;
+        // SAFETY: the map is read-only. As with any mapped file, other processes must not
+        // truncate or modify it while it is mapped.
+        let mmap = unsafe { Mmap::map(&file)? };
+        let invalid = |reason: String| Err(FormatError::InvalidHeader(reason));
+
+        if cfg!(target_endian = "big") {
+            return invalid("mapped lists require a little-endian platform".to_string());
+        }
+        let header = FileHeader::read_from(&mut &mmap[..])?;
+        let width = mem::size_of::<T>();
+        if header.element_width as usize != width {
+            return invalid(format!(
+                "element width {} doesn't match the {}-byte element type",
+                header.element_width, width
+            ));
+        }
+
+        let mut starts = Vec::with_capacity(header.directory.len());
+        let mut start = 0usize;
+        for (index, entry) in header.directory.iter().enumerate() {
+            let end = entry.offset.checked_add(entry.length);
+            if end.is_none_or(|end| end > mmap.len() as u64) {
+                return invalid(format!("chunk #{} extends past the end of the file", index));
+            }
+            if entry.count.checked_mul(width as u64) != Some(entry.length) {
+                return invalid(format!("chunk #{} length doesn't match its element count", index));
+            }
+            if !(mmap.as_ptr() as usize + entry.offset as usize).is_multiple_of(mem::align_of::<T>()) {
+                return invalid(format!("chunk #{} is not aligned for the element type", index));
+            }
+            starts.push(start);
+            start = match usize::try_from(entry.count).ok().and_then(|count| count.checked_add(start)) {
+                Some(end) => end,
+                None => return invalid(format!("chunk #{} element count overflows", index)),
+            };
+        }
+
+        Ok(Self {
+            mmap,
+            header,
+            starts,
+            _marker: PhantomData,
+        })
+    }
+
+    /// The elements of one chunk, viewed in place.
+    pub fn chunk(&self, index: usize) -> &[T] {
+        let entry = &self.header.directory[index];
+        // SAFETY: `open` checked that the payload is in bounds, aligned for `T` and
+        // exactly `count` elements long, and `T: Pod` is valid for any bytes.
+        unsafe {
+            std::slice::from_raw_parts(
+                self.mmap.as_ptr().add(entry.offset as usize) as *const T,
+                entry.count as usize,
+            )
+        }
+    }
+
+    /// Iterate over the chunks in order.
+    pub fn chunks(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.chunk_amount()).map(|index| self.chunk(index))
+    }
+
+    /// Parallel iterator over the chunks, for custom scans.
+    pub fn par_chunks(&self) -> impl IndexedParallelIterator<Item = &[T]> {
+        (0..self.chunk_amount()).into_par_iter().map(|index| self.chunk(index))
+    }
+
+    /// Get an item at a particular index.
+    pub fn get(&self, index: usize) -> &T {
+        if index >= self.len() {
+            panic!("Index out of range");
+        }
+        // Last chunk starting at or before `index` (empty chunks share a start)
+        let chunk_index = self.starts.partition_point(|&start| start <= index) - 1;
+        &self.chunk(chunk_index)[index - self.starts[chunk_index]]
+    }
+
+    /// Iterate over all elements in order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.chunks().flat_map(|chunk| chunk.iter())
+    }
+
+    /// Return a new Vec containing all elements (in order).
+    pub fn get_list(&self) -> Vec<T> {
+        self.iter().copied().collect()
+    }
+
+    /// Return the total number of elements.
+    pub fn len(&self) -> usize {
+        self.header.element_count as usize
+    }
+
+    /// Check if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the chunk size the file was written with
+    pub fn get_chunk_size(&self) -> usize {
+        self.header.chunk_size as usize
+    }
+
+    /// Get amount of chunks in the file
+    pub fn chunk_amount(&self) -> usize {
+        self.header.directory.len()
+    }
+}
+
+impl<T: Pod + PartialEq> MappedChunkList<T> {
+    /// Check if the list contains a given item, scanning the chunks in parallel.
+    pub fn contains(&self, t: &T) -> bool {
+        self.par_chunks().any(|chunk| chunk.contains(t))
+    }
+}
+
+impl<T> MappedChunkList<T>
+where T: Pod + Ord + Debug, {
+    /// Copy the mapped data into a regular, mutable ChunkList with the same layout.
+    pub fn to_chunk_list(&self) -> ChunkList<T> {
+        let mut list = ChunkList::new(self.get_chunk_size());
+        list.my_list = self.chunks().map(|chunk| Arc::new(chunk.to_vec())).collect();
+        list
+    }
+}
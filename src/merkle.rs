@@ -0,0 +1,347 @@
+use crate::format::{decode_chunk, ChunkCodec, ChunkEntry, DefaultCodec, FormatError};
+use crate::ChunkList;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::fmt::{self, Debug, Display};
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+/// Patch magic, the first four bytes of every encoded patch.
+pub const PATCH_MAGIC: [u8; 4] = *b"CHKP";
+
+// Hash of a missing node (past the end of a level)
+const EMPTY: u64 = 0;
+// Stand-in leaf hash for a chunk two lists share, which is equal without hashing it
+const SHARED: u64 = 1;
+
+/// FNV-1a, 64-bit: stable across platforms and releases, unlike `DefaultHasher`.
+/// Not collision resistant against an adversary.
+fn fnv1a(prefix: u8, bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for &b in std::iter::once(&prefix).chain(bytes) {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+fn hash_leaf(payload: &[u8]) -> u64 {
+    fnv1a(0, payload)
+}
+
+fn hash_node(left: u64, right: u64) -> u64 {
+    if left == EMPTY && right == EMPTY {
+        return EMPTY;
+    }
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&left.to_le_bytes());
+    bytes[8..].copy_from_slice(&right.to_le_bytes());
+    fnv1a(1, &bytes)
+}
+
+fn encode_chunk<T, C: ChunkCodec<T>>(chunk: &[T], codec: &C) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for item in chunk {
+        codec.encode(item, &mut payload);
+    }
+    payload
+}
+
+/// Content hashes of every chunk of a list, rolled up pairwise into a single root.
+///
+/// Two lists with the same chunk layout and contents have the same root. Comparing two
+/// trees only descends into subtrees whose hashes differ, so `diff` costs
+/// O(changed chunks · log chunks) instead of looking at every chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    // levels[0] holds the chunk hashes; every level above halves it, up to the root
+    levels: Vec<Vec<u64>>,
+}
+
+impl MerkleTree {
+    fn from_leaves(leaves: Vec<u64>) -> Self {
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let below = levels.last().unwrap();
+            let level = below
+                .chunks(2)
+                .map(|pair| hash_node(pair[0], pair.get(1).copied().unwrap_or(EMPTY)))
+                .collect();
+            levels.push(level);
+        }
+        Self { levels }
+    }
+
+    /// The root hash, covering every chunk.
+    pub fn root(&self) -> u64 {
+        self.node(self.levels.len() - 1, 0)
+    }
+
+    /// Get amount of chunks the tree covers
+    pub fn chunk_amount(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// The hash of one chunk.
+    pub fn chunk_hash(&self, index: usize) -> u64 {
+        self.levels[0][index]
+    }
+
+    /// Hash of node `index` at `level`, also for levels above this tree's root, so trees
+    /// of different heights can be compared.
+    fn node(&self, level: usize, index: usize) -> u64 {
+        match self.levels.get(level) {
+            Some(nodes) => nodes.get(index).copied().unwrap_or(EMPTY),
+            None if index == 0 => hash_node(self.node(level - 1, 0), EMPTY),
+            None => EMPTY,
+        }
+    }
+
+    /// Indices of the chunks that differ between the two trees, in ascending order.
+    /// Chunks that only exist in one of them count as different.
+    pub fn diff(&self, other: &MerkleTree) -> Vec<usize> {
+        let top = self.levels.len().max(other.levels.len()) - 1;
+        let mut changed = Vec::new();
+        let mut stack = vec![(top, 0)];
+        while let Some((level, index)) = stack.pop() {
+            if self.node(level, index) == other.node(level, index) {
+                continue;
+            }
+            if level == 0 {
+                changed.push(index);
+            } else {
+                // Right first, so chunks come off the stack in ascending order
+                stack.push((level - 1, index * 2 + 1));
+                stack.push((level - 1, index * 2));
+            }
+        }
+        changed
+    }
+
+    /// Write the chunk hashes, e.g. to send them to the node holding the other replica.
+    pub fn write_to(&self, mut w: impl Write) -> Result<(), FormatError> {
+        w.write_all(&(self.chunk_amount() as u64).to_le_bytes())?;
+        for hash in &self.levels[0] {
+            w.write_all(&hash.to_le_bytes())?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Read chunk hashes written by `write_to` and rebuild the tree.
+    pub fn read_from(mut r: impl Read) -> Result<Self, FormatError> {
+        let chunk_count = read_u64(&mut r)?;
+        let mut leaves = Vec::new();
+        for _ in 0..chunk_count {
+            leaves.push(read_u64(&mut r)?);
+        }
+        Ok(Self::from_leaves(leaves))
+    }
+}
+
+fn read_u64(r: &mut impl Read) -> std::io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// The chunks a replica is missing, to bring it to the same state as the list the patch
+/// was made from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkPatch<T> {
+    /// Chunk size of the source list.
+    pub chunk_size: usize,
+    /// Number of chunks in the source list.
+    pub chunk_count: usize,
+    /// Merkle root of the source list.
+    pub root: u64,
+    /// The differing chunks, by index, in ascending order.
+    pub chunks: Vec<(usize, Vec<T>)>,
+}
+
+/// Error applying a `ChunkPatch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// The patch has a chunk beyond its own chunk count.
+    InvalidChunk { index: usize, chunk_count: usize },
+    /// After applying the patch the list still differs from the source, e.g. because the
+    /// patch was made against a different state of the replica.
+    RootMismatch { expected: u64, actual: u64 },
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::InvalidChunk { index, chunk_count } => {
+                write!(f, "patch chunk #{} out of range for {} chunks", index, chunk_count)
+            }
+            PatchError::RootMismatch { expected, actual } => {
+                write!(f, "patched root {:016x} doesn't match the source root {:016x}", actual, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl<T> ChunkPatch<T> {
+    /// Encode the patch with the given codec.
+    ///
+    /// Layout: `PATCH_MAGIC`, then chunk_size, chunk_count, root and the number of
+    /// chunks as u64, then for every chunk its index, element count and payload length
+    /// (u64 each) followed by the payload.
+    pub fn write_with<W, C>(&self, mut w: W, codec: &C) -> Result<(), FormatError>
+    where W: Write, C: ChunkCodec<T>, {
+        w.write_all(&PATCH_MAGIC)?;
+        for value in [self.chunk_size as u64, self.chunk_count as u64, self.root, self.chunks.len() as u64] {
+            w.write_all(&value.to_le_bytes())?;
+        }
+        for (index, chunk) in &self.chunks {
+            let payload = encode_chunk(chunk, codec);
+            for value in [*index as u64, chunk.len() as u64, payload.len() as u64] {
+                w.write_all(&value.to_le_bytes())?;
+            }
+            w.write_all(&payload)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Decode a patch written by `write_with`.
+    pub fn read_with<R, C>(mut r: R, codec: &C) -> Result<Self, FormatError>
+    where R: Read, C: ChunkCodec<T>, {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if magic != PATCH_MAGIC {
+            return Err(FormatError::BadMagic);
+        }
+        let chunk_size = read_u64(&mut r)? as usize;
+        let chunk_count = read_u64(&mut r)? as usize;
+        let root = read_u64(&mut r)?;
+        let patched = read_u64(&mut r)?;
+        let mut chunks = Vec::new();
+        for _ in 0..patched {
+            let index = read_u64(&mut r)? as usize;
+            let count = read_u64(&mut r)?;
+            let length = read_u64(&mut r)?;
+            let mut payload = Vec::new();
+            r.by_ref().take(length).read_to_end(&mut payload)?;
+            if payload.len() as u64 != length {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            let entry = ChunkEntry { offset: 0, length, count };
+            chunks.push((index, decode_chunk(index, &entry, &payload, codec)?));
+        }
+        Ok(Self {
+            chunk_size,
+            chunk_count,
+            root,
+            chunks,
+        })
+    }
+}
+
+impl<T> ChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Hash every chunk's encoding with the given codec (in parallel) and build the tree.
+    pub fn merkle_tree_with<C>(&self, codec: &C) -> MerkleTree
+    where C: ChunkCodec<T> + Sync, {
+        let leaf = |chunk: &Arc<Vec<T>>| hash_leaf(&encode_chunk(chunk, codec));
+        #[cfg(feature = "rayon")]
+        let leaves = self.my_list.par_iter().map(leaf).collect();
+        #[cfg(not(feature = "rayon"))]
+        let leaves = self.my_list.iter().map(leaf).collect();
+        MerkleTree::from_leaves(leaves)
+    }
+
+    /// Indices of the chunks that differ from `other`, in ascending order. Chunks the
+    /// two lists still share (e.g. after `other` was made from a snapshot of this list)
+    /// aren't encoded or hashed at all, so only chunks written since cost anything.
+    pub fn diff_with<C>(&self, other: &ChunkList<T>, codec: &C) -> Vec<usize>
+    where C: ChunkCodec<T> + Sync, {
+        let shared = |index: usize| match (self.my_list.get(index), other.my_list.get(index)) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        };
+        let leaf = |(index, chunk): (usize, &Arc<Vec<T>>)| match shared(index) {
+            true => SHARED,
+            false => hash_leaf(&encode_chunk(chunk, codec)),
+        };
+        #[cfg(feature = "rayon")]
+        let leaves = |list: &ChunkList<T>| -> Vec<u64> { list.my_list.par_iter().enumerate().map(leaf).collect() };
+        #[cfg(not(feature = "rayon"))]
+        let leaves = |list: &ChunkList<T>| -> Vec<u64> { list.my_list.iter().enumerate().map(leaf).collect() };
+        MerkleTree::from_leaves(leaves(self)).diff(&MerkleTree::from_leaves(leaves(other)))
+    }
+
+    /// Make a patch that turns a replica with tree `replica` into a copy of this list.
+    pub fn patch_for_with<C>(&self, replica: &MerkleTree, codec: &C) -> ChunkPatch<T>
+    where C: ChunkCodec<T> + Sync, {
+        let tree = self.merkle_tree_with(codec);
+        let chunks = tree
+            .diff(replica)
+            .into_iter()
+            .take_while(|&index| index < self.my_list.len())
+            .map(|index| (index, self.my_list[index].to_vec()))
+            .collect();
+        ChunkPatch {
+            chunk_size: self.chunk_size,
+            chunk_count: self.my_list.len(),
+            root: tree.root(),
+            chunks,
+        }
+    }
+
+    /// Apply a patch made by `patch_for_with`. The list is left unchanged if the result
+    /// wouldn't match the source list.
+    pub fn apply_patch_with<C>(&mut self, patch: ChunkPatch<T>, codec: &C) -> Result<(), PatchError>
+    where C: ChunkCodec<T> + Sync, {
+        let mut chunks = self.my_list.clone();
+        chunks.resize_with(patch.chunk_count, || Arc::new(Vec::new()));
+        for (index, chunk) in patch.chunks {
+            if index >= patch.chunk_count {
+                return Err(PatchError::InvalidChunk {
+                    index,
+                    chunk_count: patch.chunk_count,
+                });
+            }
+            chunks[index] = Arc::new(chunk);
+        }
+
+        let mut patched = ChunkList::new(patch.chunk_size);
+        patched.my_list = chunks;
+        let actual = patched.merkle_tree_with(codec).root();
+        if actual != patch.root {
+            return Err(PatchError::RootMismatch {
+                expected: patch.root,
+                actual,
+            });
+        }
+        self.chunk_size = patch.chunk_size;
+        self.replace_chunks(patched.my_list);
+        Ok(())
+    }
+}
+
+impl<T> ChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone + DefaultCodec, T::Codec: Sync, {
+    /// Hash every chunk and build the tree.
+    pub fn merkle_tree(&self) -> MerkleTree {
+        self.merkle_tree_with(&T::Codec::default())
+    }
+
+    /// Indices of the chunks that differ from `other`, in ascending order (see `diff_with`).
+    pub fn diff(&self, other: &ChunkList<T>) -> Vec<usize> {
+        self.diff_with(other, &T::Codec::default())
+    }
+
+    /// Make a patch that turns a replica with tree `replica` into a copy of this list.
+    pub fn patch_for(&self, replica: &MerkleTree) -> ChunkPatch<T> {
+        self.patch_for_with(replica, &T::Codec::default())
+    }
+
+    /// Apply a patch made by `patch_for`.
+    pub fn apply_patch(&mut self, patch: ChunkPatch<T>) -> Result<(), PatchError> {
+        self.apply_patch_with(patch, &T::Codec::default())
+    }
+}
@@ -0,0 +1,104 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A change made to a ChunkList, as delivered to observers.
+///
+/// Element events carry the global index at the time of the change: replaying the
+/// events in order on a plain `Vec` (remove at `index`, insert at `index`, ...)
+/// reproduces the list's contents. Layout events (`ChunkSplit`, `Rebalanced`) don't
+/// move elements between global indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkEvent<T> {
+    /// `value` now sits at `index`; later elements moved back by one.
+    Inserted { index: usize, value: T },
+    /// `value` was removed from `index`; later elements moved forward by one.
+    Removed { index: usize, value: T },
+    /// The element at `index` was replaced.
+    Updated { index: usize, old: T, new: T },
+    /// Chunk `chunk_index` overflowed and its elements from `at` on moved into a new
+    /// chunk right after it.
+    ChunkSplit { chunk_index: usize, at: usize },
+    /// The chunk size changed and elements were redistributed over the chunks.
+    Rebalanced { old_chunk_size: usize, new_chunk_size: usize },
+    /// The list was sorted.
+    Sorted,
+    /// Every element was removed.
+    Cleared,
+}
+
+/// Handle returned by `subscribe`, used to unsubscribe again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type EachFn<T> = Box<dyn FnMut(&ChunkEvent<T>) + Send + Sync>;
+type BatchFn<T> = Box<dyn FnMut(&[ChunkEvent<T>]) + Send + Sync>;
+
+enum Subscriber<T> {
+    /// Called once per event.
+    Each(EachFn<T>),
+    /// Called once per operation, with all of its events.
+    Batch(BatchFn<T>),
+}
+
+/// The observers registered on a ChunkList.
+pub(crate) struct Observers<T> {
+    next_id: u64,
+    subscribers: Vec<(SubscriptionId, Subscriber<T>)>,
+}
+
+impl<T> Default for Observers<T> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            subscribers: Vec::new(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Observers<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.subscribers.len())
+    }
+}
+
+impl<T> Observers<T> {
+    fn push(&mut self, subscriber: Subscriber<T>) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscribers.push((id, subscriber));
+        id
+    }
+
+    pub(crate) fn subscribe(&mut self, f: EachFn<T>) -> SubscriptionId {
+        self.push(Subscriber::Each(f))
+    }
+
+    pub(crate) fn subscribe_batched(&mut self, f: BatchFn<T>) -> SubscriptionId {
+        self.push(Subscriber::Batch(f))
+    }
+
+    pub(crate) fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let before = self.subscribers.len();
+        self.subscribers.retain(|(sub_id, _)| *sub_id != id);
+        self.subscribers.len() != before
+    }
+
+    /// Whether anyone is listening. Mutating methods skip building events otherwise.
+    pub(crate) fn is_active(&self) -> bool {
+        !self.subscribers.is_empty()
+    }
+
+    /// Deliver the events of one operation to every subscriber.
+    pub(crate) fn emit(&mut self, events: Vec<ChunkEvent<T>>) {
+        if events.is_empty() {
+            return;
+        }
+        for (_, subscriber) in &mut self.subscribers {
+            match subscriber {
+                Subscriber::Each(f) => events.iter().for_each(f),
+                Subscriber::Batch(f) => f(&events),
+            }
+        }
+    }
+}
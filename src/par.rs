@@ -0,0 +1,449 @@
+use crate::ChunkList;
+use rayon::iter::plumbing::{bridge, Consumer, Producer, ProducerCallback, UnindexedConsumer};
+use rayon::prelude::*;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// A contiguous run of elements handed out by a producer: a chunk, or part of one.
+trait Piece: Sized + Send {
+    type Item;
+    type Iter: DoubleEndedIterator<Item = Self::Item> + ExactSizeIterator;
+
+    fn len(&self) -> usize;
+    fn split_at(self, index: usize) -> (Self, Self);
+    fn items(self) -> Self::Iter;
+}
+
+impl<'a, T: Sync> Piece for &'a [T] {
+    type Item = &'a T;
+    type Iter = std::slice::Iter<'a, T>;
+
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        <[T]>::split_at(self, index)
+    }
+
+    fn items(self) -> Self::Iter {
+        self.iter()
+    }
+}
+
+impl<'a, T: Send> Piece for &'a mut [T] {
+    type Item = &'a mut T;
+    type Iter = std::slice::IterMut<'a, T>;
+
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        self.split_at_mut(index)
+    }
+
+    fn items(self) -> Self::Iter {
+        self.iter_mut()
+    }
+}
+
+impl<T: Send> Piece for Vec<T> {
+    type Item = T;
+    type Iter = std::vec::IntoIter<T>;
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn split_at(mut self, index: usize) -> (Self, Self) {
+        let tail = self.split_off(index);
+        (self, tail)
+    }
+
+    fn items(self) -> Self::Iter {
+        self.into_iter()
+    }
+}
+
+/// Producer over a run of chunks. A split only divides a chunk when the requested index
+/// falls inside it; every other chunk is handed out whole.
+struct ChunkProducer<S> {
+    pieces: Vec<S>,
+    len: usize,
+}
+
+impl<S: Piece> Producer for ChunkProducer<S> {
+    type Item = S::Item;
+    type IntoIter = ChunkIter<S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ChunkIter {
+            pieces: self.pieces.into_iter(),
+            front: None,
+            back: None,
+            len: self.len,
+        }
+    }
+
+    fn split_at(mut self, index: usize) -> (Self, Self) {
+        // Find the chunk holding `index`, and the position inside it
+        let mut chunk_index = 0;
+        let mut pos = index;
+        while chunk_index < self.pieces.len() && pos >= self.pieces[chunk_index].len() {
+            pos -= self.pieces[chunk_index].len();
+            chunk_index += 1;
+        }
+        let mut right = self.pieces.split_off(chunk_index);
+        if pos > 0 {
+            let (head, tail) = right.remove(0).split_at(pos);
+            self.pieces.push(head);
+            right.insert(0, tail);
+        }
+        let left = ChunkProducer { pieces: self.pieces, len: index };
+        let right = ChunkProducer { pieces: right, len: self.len - index };
+        (left, right)
+    }
+}
+
+/// Sequential iterator over what's left of a producer once it's done splitting.
+struct ChunkIter<S: Piece> {
+    pieces: std::vec::IntoIter<S>,
+    front: Option<S::Iter>,
+    back: Option<S::Iter>,
+    len: usize,
+}
+
+impl<S: Piece> Iterator for ChunkIter<S> {
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.front.as_mut().and_then(Iterator::next) {
+                self.len -= 1;
+                return Some(item);
+            }
+            match self.pieces.next() {
+                Some(piece) => self.front = Some(piece.items()),
+                None => {
+                    let item = self.back.as_mut()?.next();
+                    if item.is_some() {
+                        self.len -= 1;
+                    }
+                    return item;
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<S: Piece> DoubleEndedIterator for ChunkIter<S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.back.as_mut().and_then(DoubleEndedIterator::next_back) {
+                self.len -= 1;
+                return Some(item);
+            }
+            match self.pieces.next_back() {
+                Some(piece) => self.back = Some(piece.items()),
+                None => {
+                    let item = self.front.as_mut()?.next_back();
+                    if item.is_some() {
+                        self.len -= 1;
+                    }
+                    return item;
+                }
+            }
+        }
+    }
+}
+
+impl<S: Piece> ExactSizeIterator for ChunkIter<S> {}
+
+macro_rules! chunk_par_iter {
+    ($(#[$doc:meta])* $name:ident<$($lt:lifetime,)? $t:ident: $bound:ident>, $piece:ty, $item:ty) => {
+        $(#[$doc])*
+        pub struct $name<$($lt,)? $t> {
+            pieces: Vec<$piece>,
+            len: usize,
+        }
+
+        impl<$($lt,)? $t: $bound $(+ $lt)?> ParallelIterator for $name<$($lt,)? $t> {
+            type Item = $item;
+
+            fn drive_unindexed<C>(self, consumer: C) -> C::Result
+            where C: UnindexedConsumer<Self::Item>, {
+                bridge(self, consumer)
+            }
+
+            fn opt_len(&self) -> Option<usize> {
+                Some(self.len)
+            }
+        }
+
+        impl<$($lt,)? $t: $bound $(+ $lt)?> IndexedParallelIterator for $name<$($lt,)? $t> {
+            fn drive<C>(self, consumer: C) -> C::Result
+            where C: Consumer<Self::Item>, {
+                bridge(self, consumer)
+            }
+
+            fn len(&self) -> usize {
+                self.len
+            }
+
+            fn with_producer<CB>(self, callback: CB) -> CB::Output
+            where CB: ProducerCallback<Self::Item>, {
+                callback.callback(ChunkProducer {
+                    pieces: self.pieces,
+                    len: self.len,
+                })
+            }
+        }
+    };
+}
+
+chunk_par_iter!(
+    /// Parallel iterator over references to the elements of a ChunkList, in order.
+    ParIter<'a, T: Sync>, &'a [T], &'a T
+);
+chunk_par_iter!(
+    /// Parallel iterator over mutable references to the elements of a ChunkList, in order.
+    ParIterMut<'a, T: Send>, &'a mut [T], &'a mut T
+);
+chunk_par_iter!(
+    /// Parallel iterator that moves the elements out of a ChunkList, in order.
+    IntoParIter<T: Send>, Vec<T>, T
+);
+
+impl<'a, T: Sync + 'a> IntoParallelIterator for &'a ChunkList<T> {
+    type Iter = ParIter<'a, T>;
+    type Item = &'a T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParIter {
+            pieces: self.my_list.iter().map(|chunk| chunk.as_slice()).collect(),
+            len: self.my_list.iter().map(|chunk| chunk.len()).sum(),
+        }
+    }
+}
+
+/// Chunks shared with a snapshot are copied first. Changes made through the iterator
+/// aren't reported to observers.
+impl<'a, T: Send + Clone + 'a> IntoParallelIterator for &'a mut ChunkList<T> {
+    type Iter = ParIterMut<'a, T>;
+    type Item = &'a mut T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        let len = self.my_list.iter().map(|chunk| chunk.len()).sum();
+        ParIterMut {
+            pieces: self.my_list.iter_mut().map(|chunk| Arc::make_mut(chunk).as_mut_slice()).collect(),
+            len,
+        }
+    }
+}
+
+/// Chunks shared with a snapshot are cloned, the others are moved out.
+impl<T: Send + Clone> IntoParallelIterator for ChunkList<T> {
+    type Iter = IntoParIter<T>;
+    type Item = T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        let len = self.my_list.iter().map(|chunk| chunk.len()).sum();
+        IntoParIter {
+            pieces: self.my_list.into_iter().map(Arc::unwrap_or_clone).collect(),
+            len,
+        }
+    }
+}
+
+/// Gather the items into chunks of at most `chunk_size`, in parallel and in order. Each
+/// task fills its own chunks, so a chunk may end short where two tasks meet.
+fn collect_chunks<T, I>(par_iter: I, chunk_size: usize) -> Vec<Vec<T>>
+where T: Send, I: IntoParallelIterator<Item = T>, {
+    par_iter
+        .into_par_iter()
+        .fold(Vec::new, |mut chunks: Vec<Vec<T>>, item| {
+            match chunks.last_mut() {
+                Some(chunk) if chunk.len() < chunk_size => chunk.push(item),
+                _ => {
+                    let mut chunk = Vec::with_capacity(chunk_size);
+                    chunk.push(item);
+                    chunks.push(chunk);
+                }
+            }
+            chunks
+        })
+        .reduce(Vec::new, |mut left, mut right| {
+            left.append(&mut right);
+            left
+        })
+}
+
+/// Collects into a list with the default chunk size of 1000.
+impl<T> FromParallelIterator<T> for ChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where I: IntoParallelIterator<Item = T>, {
+        let mut list = ChunkList::default();
+        list.par_extend(par_iter);
+        list
+    }
+}
+
+/// New elements go into new chunks after the last one, in order. Where the chunks end
+/// depends on how the work was split, except in deterministic mode, where every new chunk
+/// but the last is full.
+impl<T> ParallelExtend<T> for ChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    fn par_extend<I>(&mut self, par_iter: I)
+    where I: IntoParallelIterator<Item = T>, {
+        let chunk_size = self.chunk_size.max(1);
+        let chunks = if self.execution.is_deterministic() {
+            let items: Vec<T> = par_iter.into_par_iter().collect();
+            let mut items = items.into_iter();
+            let mut chunks = Vec::new();
+            while items.len() > 0 {
+                chunks.push(items.by_ref().take(chunk_size).collect());
+            }
+            chunks
+        } else {
+            collect_chunks(par_iter, chunk_size)
+        };
+        self.append_chunks(chunks);
+    }
+}
+
+impl<T> ChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Build a new list by applying `f` to every element. Each chunk is mapped by one task,
+    /// so the result has the same chunk layout.
+    pub fn map<U, F>(&self, f: F) -> ChunkList<U>
+    where U: Ord + Debug + Send + Sync + Clone, F: Fn(&T) -> U + Sync + Send, {
+        let mut list = ChunkList::new(self.chunk_size);
+        list.my_list = self.execution.run(self.len(), |min_len| {
+            self.my_list
+                .par_iter()
+                .with_min_len(min_len)
+                .map(|chunk| Arc::new(chunk.iter().map(&f).collect()))
+                .collect()
+        });
+        list
+    }
+
+    /// Build a new list of the elements matching `pred`, one task per chunk. Chunks keep
+    /// their order; chunks left empty are dropped, and chunks with every element kept are
+    /// shared with this list instead of copied.
+    pub fn filter<F>(&self, pred: F) -> ChunkList<T>
+    where F: Fn(&T) -> bool + Sync + Send, {
+        let mut list = ChunkList::new(self.chunk_size);
+        list.my_list = self.execution.run(self.len(), |min_len| {
+            self.my_list
+                .par_iter()
+                .with_min_len(min_len)
+                .filter_map(|chunk| {
+                    let kept: Vec<T> = chunk.iter().filter(|item| pred(item)).cloned().collect();
+                    match kept.len() {
+                        0 => None,
+                        n if n == chunk.len() => Some(chunk.clone()),
+                        _ => Some(Arc::new(kept)),
+                    }
+                })
+                .collect()
+        });
+        list
+    }
+
+    /// Run `f` on every chunk in parallel, with the chunk's index and its elements.
+    /// Changed elements are reported to observers as updates.
+    pub fn par_for_each_chunk<F>(&mut self, f: F)
+    where F: Fn(usize, &mut [T]) + Sync + Send, {
+        let old = self.is_observed().then(|| self.my_list.clone());
+        let work = self.len();
+        self.execution.run(work, |min_len| {
+            self.my_list
+                .par_iter_mut()
+                .enumerate()
+                .with_min_len(min_len)
+                .for_each(|(chunk_index, chunk)| f(chunk_index, Arc::make_mut(chunk).as_mut_slice()))
+        });
+        if let Some(old) = old {
+            self.report_updates(old);
+        }
+    }
+
+    /// Fold every chunk from `identity()` with `fold`, in parallel, then combine the
+    /// per-chunk results with `combine` in list order. `combine` has to be associative,
+    /// but needn't be commutative.
+    ///
+    /// Results are combined as tasks finish, so with an only nearly associative `combine`
+    /// (floating point sums) the result can vary between runs. In deterministic mode they
+    /// are combined one after another, from the first chunk to the last.
+    pub fn fold<R, ID, F, C>(&self, identity: ID, fold: F, combine: C) -> R
+    where R: Send, ID: Fn() -> R + Sync + Send, F: Fn(R, &T) -> R + Sync + Send, C: Fn(R, R) -> R + Sync + Send, {
+        let fold_chunk = |chunk: &Arc<Vec<T>>| chunk.iter().fold(identity(), &fold);
+        if self.execution.is_deterministic() {
+            let folded: Vec<R> =
+                self.execution.run(self.len(), |min_len| self.my_list.par_iter().with_min_len(min_len).map(fold_chunk).collect());
+            return folded.into_iter().fold(identity(), &combine);
+        }
+        self.execution.run(self.len(), |min_len| {
+            self.my_list
+                .par_iter()
+                .with_min_len(min_len)
+                .map(fold_chunk)
+                .reduce(&identity, &combine)
+        })
+    }
+
+    /// Combine all elements with `op` in list order, in parallel. `op` has to be
+    /// associative, but needn't be commutative. Returns None if the list is empty.
+    /// Chunk results are combined like in `fold`.
+    pub fn reduce<F>(&self, op: F) -> Option<T>
+    where F: Fn(T, T) -> T + Sync + Send, {
+        let reduce_chunk = |chunk: &Arc<Vec<T>>| chunk.iter().cloned().reduce(&op);
+        if self.execution.is_deterministic() {
+            let reduced: Vec<T> =
+                self.execution.run(self.len(), |min_len| self.my_list.par_iter().with_min_len(min_len).filter_map(reduce_chunk).collect());
+            return reduced.into_iter().reduce(&op);
+        }
+        self.execution.run(self.len(), |min_len| {
+            self.my_list
+                .par_iter()
+                .with_min_len(min_len)
+                .filter_map(reduce_chunk)
+                .reduce_with(&op)
+        })
+    }
+
+    /// Old name of `map`.
+    #[deprecated(note = "renamed to `map`")]
+    pub fn par_map<U, F>(&self, f: F) -> ChunkList<U>
+    where U: Ord + Debug + Send + Sync + Clone, F: Fn(&T) -> U + Sync + Send, {
+        self.map(f)
+    }
+
+    /// Old name of `filter`.
+    #[deprecated(note = "renamed to `filter`")]
+    pub fn par_filter<F>(&self, pred: F) -> ChunkList<T>
+    where F: Fn(&T) -> bool + Sync + Send, {
+        self.filter(pred)
+    }
+
+    /// Old name of `fold`.
+    #[deprecated(note = "renamed to `fold`")]
+    pub fn par_fold<R, ID, F, C>(&self, identity: ID, fold: F, combine: C) -> R
+    where R: Send, ID: Fn() -> R + Sync + Send, F: Fn(R, &T) -> R + Sync + Send, C: Fn(R, R) -> R + Sync + Send, {
+        self.fold(identity, fold, combine)
+    }
+
+    /// Old name of `reduce`.
+    #[deprecated(note = "renamed to `reduce`")]
+    pub fn par_reduce<F>(&self, op: F) -> Option<T>
+    where F: Fn(T, T) -> T + Sync + Send, {
+        self.reduce(op)
+    }
+}
@@ -0,0 +1,191 @@
+use crate::ChunkList;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+/// An immutable ChunkList with structural sharing.
+///
+/// Every "mutating" method returns a new list and leaves `self` untouched. The new
+/// list shares all unchanged chunks with the original via `Arc`, so only the touched
+/// chunk is copied, and cloning a list only copies one pointer per chunk.
+#[derive(Debug, Clone)]
+pub struct PersistentChunkList<T> {
+    chunks: Vec<Arc<Vec<T>>>,
+    chunk_size: usize,
+    len: usize,
+}
+
+impl<T> Default for PersistentChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Default constructor with chunk size = 1000
+    fn default() -> Self {
+        Self::new(1000)
+    }
+}
+
+impl<T> PersistentChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Creates a new, empty PersistentChunkList with the specified chunk size.
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunks: Vec::new(),
+            chunk_size,
+            len: 0,
+        }
+    }
+
+    /// Helper: Convert a global index to (chunk_index, position_in_chunk).
+    fn locate(&self, index: usize) -> Option<(usize, usize)> {
+        let mut remaining = index;
+        for (chunk_index, chunk) in self.chunks.iter().enumerate() {
+            if remaining < chunk.len() {
+                return Some((chunk_index, remaining));
+            }
+            remaining -= chunk.len();
+        }
+        None
+    }
+
+    /// Helper: Copy of this list whose chunk at `chunk_index` is unshared and editable.
+    fn with_chunk<F>(&self, chunk_index: usize, len: usize, edit: F) -> Self
+    where F: FnOnce(&mut Vec<T>), {
+        let mut chunks = self.chunks.clone();
+        edit(Arc::make_mut(&mut chunks[chunk_index]));
+        Self {
+            chunks,
+            chunk_size: self.chunk_size,
+            len,
+        }
+    }
+
+    /// Return a new list with `t` appended at the end.
+    pub fn push(&self, t: T) -> Self {
+        match self.chunks.last() {
+            Some(last) if last.len() < self.chunk_size => {
+                self.with_chunk(self.chunks.len() - 1, self.len + 1, |chunk| chunk.push(t))
+            }
+            _ => {
+                let mut new_chunk = Vec::with_capacity(self.chunk_size);
+                new_chunk.push(t);
+                let mut chunks = self.chunks.clone();
+                chunks.push(Arc::new(new_chunk));
+                Self {
+                    chunks,
+                    chunk_size: self.chunk_size,
+                    len: self.len + 1,
+                }
+            }
+        }
+    }
+
+    /// Return a new list with the item at `index` replaced.
+    pub fn set(&self, index: usize, t: T) -> Self {
+        match self.locate(index) {
+            Some((chunk_index, pos)) => self.with_chunk(chunk_index, self.len, |chunk| chunk[pos] = t),
+            None => panic!("Index out of range"),
+        }
+    }
+
+    /// Return a new list with `t` inserted at `index`. A chunk that overflows is split in half.
+    pub fn insert(&self, index: usize, t: T) -> Self {
+        if index > self.len {
+            panic!("Index out of range");
+        }
+        if index == self.len {
+            return self.push(t);
+        }
+        let (chunk_index, pos) = self.locate(index).unwrap();
+        let mut list = self.with_chunk(chunk_index, self.len + 1, |chunk| chunk.insert(pos, t));
+        if list.chunks[chunk_index].len() > self.chunk_size.max(1) {
+            let chunk = Arc::make_mut(&mut list.chunks[chunk_index]);
+            let tail = chunk.split_off(chunk.len() / 2);
+            list.chunks.insert(chunk_index + 1, Arc::new(tail));
+        }
+        list
+    }
+
+    /// Return a new list without the item at `index`. Chunks left empty are dropped.
+    pub fn remove_at(&self, index: usize) -> Self {
+        let (chunk_index, pos) = match self.locate(index) {
+            Some(found) => found,
+            None => panic!("Index out of range"),
+        };
+        let mut list = self.with_chunk(chunk_index, self.len - 1, |chunk| {
+            chunk.remove(pos);
+        });
+        if list.chunks[chunk_index].is_empty() {
+            list.chunks.remove(chunk_index);
+        }
+        list
+    }
+
+    /// Get an item at a particular index.
+    pub fn get(&self, index: usize) -> &T {
+        match self.locate(index) {
+            Some((chunk_index, pos)) => &self.chunks[chunk_index][pos],
+            None => panic!("Index out of range"),
+        }
+    }
+
+    /// Iterate over all elements in order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.chunks.iter().flat_map(|chunk| chunk.iter())
+    }
+
+    /// Check if the list contains a given item.
+    pub fn contains(&self, t: &T) -> bool {
+        self.chunks.iter().any(|chunk| chunk.contains(t))
+    }
+
+    /// Return a new Vec containing all elements (in order).
+    pub fn get_list(&self) -> Vec<T> {
+        self.iter().cloned().collect()
+    }
+
+    /// Return the total number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get current chunk size
+    pub fn get_chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Get amount of chunks in the list
+    pub fn chunk_amount(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Count the chunks this list physically shares with `other`.
+    pub fn shared_chunks(&self, other: &Self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| other.chunks.iter().any(|o| Arc::ptr_eq(chunk, o)))
+            .count()
+    }
+
+    /// Build a mutable ChunkList from this version, still sharing the chunks.
+    pub fn to_chunk_list(&self) -> ChunkList<T> {
+        let mut list = ChunkList::new(self.chunk_size);
+        list.my_list = self.chunks.clone();
+        list
+    }
+}
+
+impl<T> From<ChunkList<T>> for PersistentChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    fn from(list: ChunkList<T>) -> Self {
+        let len = list.len();
+        Self {
+            chunks: list.my_list.into_iter().filter(|chunk| !chunk.is_empty()).collect(),
+            chunk_size: list.chunk_size,
+            len,
+        }
+    }
+}
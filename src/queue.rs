@@ -0,0 +1,219 @@
+use crate::ChunkList;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Display};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// A blocking FIFO work queue backed by chunked storage.
+///
+/// Producers `push` onto the last chunk; consumers take single elements from the
+/// front chunk, or a whole chunk at once with `pop_chunk`. After `close`, pushes are
+/// rejected and consumers drain the remaining elements before seeing the end.
+#[derive(Debug)]
+pub struct ChunkQueue<T> {
+    state: Mutex<QueueState<T>>,
+    available: Condvar,
+    chunk_size: usize,
+}
+
+#[derive(Debug)]
+struct QueueState<T> {
+    chunks: VecDeque<VecDeque<T>>,
+    len: usize,
+    closed: bool,
+}
+
+/// Error returned by `push` on a closed queue, handing the element back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueClosed<T>(pub T);
+
+impl<T> Display for QueueClosed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "push on a closed queue")
+    }
+}
+
+impl<T: Debug> std::error::Error for QueueClosed<T> {}
+
+/// Reason a non-blocking or timed pop did not return an element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopError {
+    /// The queue was empty (`try_pop` only).
+    Empty,
+    /// No element arrived before the timeout (`pop_timeout` only).
+    Timeout,
+    /// The queue is closed and fully drained.
+    Closed,
+}
+
+impl Display for PopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PopError::Empty => write!(f, "queue is empty"),
+            PopError::Timeout => write!(f, "timed out waiting on an empty queue"),
+            PopError::Closed => write!(f, "queue is closed and drained"),
+        }
+    }
+}
+
+impl std::error::Error for PopError {}
+
+impl<T> QueueState<T> {
+    fn pop_front(&mut self) -> Option<T> {
+        let front = self.chunks.front_mut()?;
+        let t = front.pop_front();
+        if front.is_empty() {
+            self.chunks.pop_front();
+        }
+        if t.is_some() {
+            self.len -= 1;
+        }
+        t
+    }
+}
+
+impl<T> ChunkQueue<T>
+where T: Send, {
+    /// Creates a new, open ChunkQueue with the specified chunk size.
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                chunks: VecDeque::new(),
+                len: 0,
+                closed: false,
+            }),
+            available: Condvar::new(),
+            chunk_size,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Push an element onto the back of the queue, waking one waiting consumer.
+    pub fn push(&self, t: T) -> Result<(), QueueClosed<T>> {
+        let mut state = self.lock();
+        if state.closed {
+            return Err(QueueClosed(t));
+        }
+        match state.chunks.back_mut() {
+            Some(chunk) if chunk.len() < self.chunk_size => chunk.push_back(t),
+            _ => {
+                let mut new_chunk = VecDeque::with_capacity(self.chunk_size);
+                new_chunk.push_back(t);
+                state.chunks.push_back(new_chunk);
+            }
+        }
+        state.len += 1;
+        drop(state);
+        self.available.notify_one();
+        Ok(())
+    }
+
+    /// Pop the front element, blocking while the queue is empty.
+    /// Returns None once the queue is closed and drained.
+    pub fn pop(&self) -> Option<T> {
+        let state = self.lock();
+        let mut state = self
+            .available
+            .wait_while(state, |s| s.len == 0 && !s.closed)
+            .unwrap_or_else(PoisonError::into_inner);
+        state.pop_front()
+    }
+
+    /// Pop the front element, waiting at most `timeout` for one to arrive.
+    pub fn pop_timeout(&self, timeout: Duration) -> Result<T, PopError> {
+        let state = self.lock();
+        let (mut state, _) = self
+            .available
+            .wait_timeout_while(state, timeout, |s| s.len == 0 && !s.closed)
+            .unwrap_or_else(PoisonError::into_inner);
+        match state.pop_front() {
+            Some(t) => Ok(t),
+            None if state.closed => Err(PopError::Closed),
+            None => Err(PopError::Timeout),
+        }
+    }
+
+    /// Pop the front element without blocking.
+    pub fn try_pop(&self) -> Result<T, PopError> {
+        let mut state = self.lock();
+        match state.pop_front() {
+            Some(t) => Ok(t),
+            None if state.closed => Err(PopError::Closed),
+            None => Err(PopError::Empty),
+        }
+    }
+
+    /// Take the whole front chunk, blocking while the queue is empty.
+    /// Returns None once the queue is closed and drained.
+    ///
+    /// The chunk's buffer is handed over as-is: elements are never cloned or
+    /// reallocated (only shifted in place if single pops already consumed its head).
+    pub fn pop_chunk(&self) -> Option<Vec<T>> {
+        let state = self.lock();
+        let mut state = self
+            .available
+            .wait_while(state, |s| s.len == 0 && !s.closed)
+            .unwrap_or_else(PoisonError::into_inner);
+        let chunk = state.chunks.pop_front()?;
+        state.len -= chunk.len();
+        Some(Vec::from(chunk))
+    }
+
+    /// Close the queue: further pushes fail and blocked consumers wake up to drain it.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.available.notify_all();
+    }
+
+    /// Check if the queue has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// Number of queued elements.
+    pub fn len(&self) -> usize {
+        self.lock().len
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get current chunk size
+    pub fn get_chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Get amount of chunks currently queued
+    pub fn chunk_amount(&self) -> usize {
+        self.lock().chunks.len()
+    }
+}
+
+impl<T> From<ChunkList<T>> for ChunkQueue<T>
+where T: Send + Clone, {
+    /// Seed an open queue with a ChunkList's chunks, in list order.
+    /// Chunks are moved over as-is unless they are still shared with a snapshot.
+    fn from(list: ChunkList<T>) -> Self {
+        let chunks: VecDeque<VecDeque<T>> = list
+            .my_list
+            .into_iter()
+            .filter(|chunk| !chunk.is_empty())
+            .map(|chunk| VecDeque::from(Arc::unwrap_or_clone(chunk)))
+            .collect();
+        let len = chunks.iter().map(|chunk| chunk.len()).sum();
+        Self {
+            state: Mutex::new(QueueState {
+                chunks,
+                len,
+                closed: false,
+            }),
+            available: Condvar::new(),
+            chunk_size: list.chunk_size,
+        }
+    }
+}
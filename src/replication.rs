@@ -0,0 +1,489 @@
+//! Leader/follower replication over a stream socket.
+//!
+//! A follower connects and sends a hello with the state it already has. The leader
+//! answers with the operations the follower missed if it still has them in its log, or
+//! else with a snapshot of the whole list, and then streams every new operation as it is
+//! applied. Operations carry consecutive sequence numbers, so a follower that loses its
+//! connection can reconnect and catch up from where it stopped.
+//!
+//! Every follower has its own bounded queue of operations and a thread writing them out,
+//! so a slow follower never holds up the leader. A follower whose queue fills up is
+//! dropped; it catches up when it reconnects.
+//!
+//! Wire format (little-endian):
+//!
+//! ```text
+//! hello     b"CHKR", has_state u8, epoch u64, seq u64          (follower -> leader)
+//! snapshot  1u8, epoch u64, seq u64, length u64, native format (leader -> follower)
+//! op        2u8, length u32, seq u64 + encoded op              (leader -> follower)
+//! ```
+
+use crate::format::{ChunkCodec, DefaultCodec};
+use crate::sync::lock;
+use crate::wal::{decode_record, encode_record, Op};
+use crate::{ChunkList, ChunkListSnapshot};
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Hello magic, the first four bytes a follower sends.
+pub const HELLO_MAGIC: [u8; 4] = *b"CHKR";
+
+const MSG_SNAPSHOT: u8 = 1;
+const MSG_OP: u8 = 2;
+
+/// Socket connections accepted by `serve_tcp` and `serve_unix` are dropped when a write
+/// to them blocks for this long.
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn invalid_data(reason: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.into())
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// An encoded op message, shared by the log and every follower queue.
+type Message = Arc<[u8]>;
+
+struct LeaderState<T> {
+    list: ChunkList<T>,
+    seq: u64,
+    // Op messages of the latest operations, oldest first
+    log: VecDeque<(u64, Message)>,
+    // Queues of the connected followers' writer threads
+    followers: Vec<SyncSender<Message>>,
+}
+
+/// What a newly connected follower needs to be brought up to date.
+enum Bootstrap<T> {
+    /// The op messages it missed.
+    CatchUp(Vec<Message>),
+    /// The whole list, as of a sequence number.
+    Snapshot(ChunkListSnapshot<T>, u64),
+}
+
+/// Write queued messages to a follower until its queue is dropped or a write fails.
+fn write_queued(mut out: impl Write, queue: Receiver<Message>) {
+    for message in queue {
+        if out.write_all(&message).and_then(|_| out.flush()).is_err() {
+            return;
+        }
+    }
+}
+
+/// The writable side of a replicated list. Cloning gives another handle to the same leader.
+pub struct ReplicationLeader<T, C> {
+    state: Arc<Mutex<LeaderState<T>>>,
+    codec: Arc<C>,
+    // Identifies this leader, so followers of another leader re-bootstrap
+    epoch: u64,
+    log_capacity: usize,
+    queue_capacity: usize,
+}
+
+impl<T, C> Clone for ReplicationLeader<T, C> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            codec: self.codec.clone(),
+            epoch: self.epoch,
+            log_capacity: self.log_capacity,
+            queue_capacity: self.queue_capacity,
+        }
+    }
+}
+
+impl<T, C> Debug for ReplicationLeader<T, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = lock(&self.state);
+        f.debug_struct("ReplicationLeader")
+            .field("epoch", &self.epoch)
+            .field("seq", &state.seq)
+            .field("followers", &state.followers.len())
+            .finish()
+    }
+}
+
+impl<T> ReplicationLeader<T, T::Codec>
+where T: Ord + Debug + Send + Sync + Clone + DefaultCodec + 'static, T::Codec: Send + Sync + 'static, {
+    /// Start replicating `list` with the default codec.
+    pub fn new(list: ChunkList<T>) -> Self {
+        Self::with_codec(list, T::Codec::default())
+    }
+}
+
+impl<T, C> ReplicationLeader<T, C>
+where T: Ord + Debug + Send + Sync + Clone + 'static, C: ChunkCodec<T> + Send + Sync + 'static, {
+    /// Start replicating `list`, encoding elements with `codec`.
+    pub fn with_codec(list: ChunkList<T>, codec: C) -> Self {
+        // Tells followers whether they've seen this leader's log before. Always random,
+        // even in deterministic mode: a restarted leader reusing an epoch would have
+        // followers skip its operations.
+        let mut hasher = RandomState::new().build_hasher();
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+        hasher.write_u128(nanos);
+        hasher.write_u32(std::process::id());
+        let epoch = hasher.finish();
+        Self {
+            state: Arc::new(Mutex::new(LeaderState {
+                list,
+                seq: 0,
+                log: VecDeque::new(),
+                followers: Vec::new(),
+            })),
+            codec: Arc::new(codec),
+            epoch,
+            log_capacity: 10_000,
+            queue_capacity: 1024,
+        }
+    }
+
+    /// Keep the last `ops` operations for catching up followers (default 10000). Followers
+    /// that are further behind get a snapshot instead.
+    pub fn with_log_capacity(mut self, ops: usize) -> Self {
+        self.log_capacity = ops;
+        self
+    }
+
+    /// Queue at most `ops` operations for each follower (default 1024). Followers that
+    /// fall further behind are dropped, and catch up when they reconnect.
+    pub fn with_queue_capacity(mut self, ops: usize) -> Self {
+        self.queue_capacity = ops.max(1);
+        self
+    }
+
+    /// Apply an operation, log it and send it to every follower.
+    fn replicate(&self, op: Op<T>) {
+        self.replicate_locked(&mut lock(&self.state), op);
+    }
+
+    /// Helper: `replicate` with the state already locked.
+    fn replicate_locked(&self, state: &mut LeaderState<T>, op: Op<T>) {
+        let seq = state.seq + 1;
+        let body = encode_record(seq, &op, &*self.codec);
+        op.apply(&mut state.list);
+        state.seq = seq;
+
+        let mut message = Vec::with_capacity(5 + body.len());
+        message.push(MSG_OP);
+        message.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        message.extend_from_slice(&body);
+        let message: Message = message.into();
+        // Followers with a full queue are dropped, as are those whose writer has stopped
+        state.followers.retain(|follower| follower.try_send(message.clone()).is_ok());
+
+        state.log.push_back((seq, message));
+        while state.log.len() > self.log_capacity {
+            state.log.pop_front();
+        }
+    }
+
+    /// Add an item and replicate it.
+    pub fn add(&self, t: T) {
+        self.replicate(Op::Add(t));
+    }
+
+    /// Replace the item at a particular index and replicate it.
+    pub fn set(&self, index: usize, t: T) {
+        let mut state = lock(&self.state);
+        if index >= state.list.len() {
+            panic!("Index out of range");
+        }
+        self.replicate_locked(&mut state, Op::Set(index, t));
+    }
+
+    /// Remove the item at a particular index and replicate it.
+    pub fn remove_at(&self, index: usize) -> T {
+        let mut state = lock(&self.state);
+        let item = state.list.get(index).clone();
+        self.replicate_locked(&mut state, Op::RemoveAt(index));
+        item
+    }
+
+    /// Remove all occurrences of an item and replicate it.
+    pub fn remove_all(&self, t: &T) {
+        self.replicate(Op::RemoveAll(t.clone()));
+    }
+
+    /// Sort the list and replicate it.
+    pub fn sort(&self) {
+        self.replicate(Op::Sort);
+    }
+
+    /// A snapshot of the current list.
+    pub fn snapshot(&self) -> ChunkListSnapshot<T> {
+        lock(&self.state).list.snapshot()
+    }
+
+    /// Return the total number of elements.
+    pub fn len(&self) -> usize {
+        lock(&self.state).list.len()
+    }
+
+    /// Check if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sequence number of the last operation.
+    pub fn seq(&self) -> u64 {
+        lock(&self.state).seq
+    }
+
+    /// Number of connected followers.
+    pub fn follower_count(&self) -> usize {
+        lock(&self.state).followers.len()
+    }
+
+    /// Close every follower connection, once the operations already queued for it are
+    /// sent. Followers can reconnect and catch up.
+    pub fn disconnect_followers(&self) {
+        lock(&self.state).followers.clear();
+    }
+
+    /// Handshake with a newly connected follower, bring it up to date and start streaming
+    /// operations to it.
+    pub fn accept<S>(&self, mut stream: S) -> io::Result<()>
+    where S: Read + Write + Send + 'static, {
+        let mut magic = [0; 4];
+        stream.read_exact(&mut magic)?;
+        if magic != HELLO_MAGIC {
+            return Err(invalid_data("not a chunk list follower"));
+        }
+        let mut has_state = [0];
+        stream.read_exact(&mut has_state)?;
+        let epoch = read_u64(&mut stream)?;
+        let seq = read_u64(&mut stream)?;
+
+        // Register the follower's queue while holding the lock, so it can't miss an
+        // operation, but send what it's missing after letting go
+        let (sender, queue) = mpsc::sync_channel(self.queue_capacity);
+        let bootstrap = {
+            let mut state = lock(&self.state);
+            let first_logged = state.log.front().map_or(state.seq + 1, |(seq, _)| *seq);
+            let can_catch_up = has_state[0] == 1 && epoch == self.epoch && seq <= state.seq && seq + 1 >= first_logged;
+            state.followers.push(sender);
+            if can_catch_up {
+                let missed = state.log.iter().filter(|(logged, _)| *logged > seq);
+                Bootstrap::CatchUp(missed.map(|(_, message)| message.clone()).collect())
+            } else {
+                Bootstrap::Snapshot(state.list.snapshot(), state.seq)
+            }
+        };
+
+        let mut out = BufWriter::new(stream);
+        match bootstrap {
+            Bootstrap::CatchUp(messages) => {
+                for message in messages {
+                    out.write_all(&message)?;
+                }
+            }
+            Bootstrap::Snapshot(list, seq) => {
+                let mut snapshot = Vec::new();
+                list.to_chunk_list()
+                    .write_with(&mut snapshot, &*self.codec)
+                    .map_err(|e| io::Error::other(e.to_string()))?;
+                out.write_all(&[MSG_SNAPSHOT])?;
+                out.write_all(&self.epoch.to_le_bytes())?;
+                out.write_all(&seq.to_le_bytes())?;
+                out.write_all(&(snapshot.len() as u64).to_le_bytes())?;
+                out.write_all(&snapshot)?;
+            }
+        }
+        out.flush()?;
+        thread::spawn(move || write_queued(out, queue));
+        Ok(())
+    }
+
+    /// Accept followers from a TCP listener on a background thread.
+    pub fn serve_tcp(&self, listener: TcpListener) -> JoinHandle<()> {
+        let leader = self.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = stream.set_nodelay(true);
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                let leader = leader.clone();
+                // A slow handshake shouldn't hold up other followers
+                thread::spawn(move || leader.accept(stream));
+            }
+        })
+    }
+
+    /// Accept followers from a Unix domain socket listener on a background thread.
+    #[cfg(unix)]
+    pub fn serve_unix(&self, listener: std::os::unix::net::UnixListener) -> JoinHandle<()> {
+        let leader = self.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                let leader = leader.clone();
+                thread::spawn(move || leader.accept(stream));
+            }
+        })
+    }
+}
+
+struct FollowerState<T> {
+    list: ChunkList<T>,
+    // Epoch of the leader the list came from, None before the first snapshot
+    epoch: Option<u64>,
+    seq: u64,
+}
+
+/// A read-only replica of a leader's list. Cloning gives another handle to the same replica.
+pub struct ReplicationFollower<T, C> {
+    state: Arc<Mutex<FollowerState<T>>>,
+    codec: Arc<C>,
+}
+
+impl<T, C> Clone for ReplicationFollower<T, C> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            codec: self.codec.clone(),
+        }
+    }
+}
+
+impl<T, C> Debug for ReplicationFollower<T, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = lock(&self.state);
+        f.debug_struct("ReplicationFollower")
+            .field("epoch", &state.epoch)
+            .field("seq", &state.seq)
+            .finish()
+    }
+}
+
+impl<T> ReplicationFollower<T, T::Codec>
+where T: Ord + Debug + Send + Sync + Clone + DefaultCodec, {
+    /// Create an empty replica with the default codec.
+    pub fn new() -> Self {
+        Self::with_codec(T::Codec::default())
+    }
+}
+
+impl<T> Default for ReplicationFollower<T, T::Codec>
+where T: Ord + Debug + Send + Sync + Clone + DefaultCodec, {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, C> ReplicationFollower<T, C>
+where T: Ord + Debug + Send + Sync + Clone, C: ChunkCodec<T>, {
+    /// Create an empty replica, decoding elements with `codec`.
+    pub fn with_codec(codec: C) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(FollowerState {
+                list: ChunkList::new(0),
+                epoch: None,
+                seq: 0,
+            })),
+            codec: Arc::new(codec),
+        }
+    }
+
+    /// Sync over a connected stream: send the hello, then apply everything the leader
+    /// sends until it closes the connection. Call it again with a new connection to
+    /// catch up after a disconnect.
+    pub fn run<S: Read + Write>(&self, mut stream: S) -> io::Result<()> {
+        {
+            let state = lock(&self.state);
+            let mut hello = Vec::with_capacity(21);
+            hello.extend_from_slice(&HELLO_MAGIC);
+            hello.push(state.epoch.is_some() as u8);
+            hello.extend_from_slice(&state.epoch.unwrap_or(0).to_le_bytes());
+            hello.extend_from_slice(&state.seq.to_le_bytes());
+            stream.write_all(&hello)?;
+            stream.flush()?;
+        }
+
+        let mut reader = BufReader::new(stream);
+        loop {
+            let mut tag = [0];
+            match reader.read_exact(&mut tag) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e),
+            }
+            match tag[0] {
+                MSG_SNAPSHOT => {
+                    let epoch = read_u64(&mut reader)?;
+                    let seq = read_u64(&mut reader)?;
+                    let length = read_u64(&mut reader)?;
+                    let mut snapshot = Vec::new();
+                    (&mut reader).take(length).read_to_end(&mut snapshot)?;
+                    if snapshot.len() as u64 != length {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+                    let list = ChunkList::read_with(snapshot.as_slice(), &*self.codec)
+                        .map_err(|e| invalid_data(e.to_string()))?;
+                    let mut state = lock(&self.state);
+                    *state = FollowerState {
+                        list,
+                        epoch: Some(epoch),
+                        seq,
+                    };
+                }
+                MSG_OP => {
+                    let mut length = [0; 4];
+                    reader.read_exact(&mut length)?;
+                    let mut body = vec![0; u32::from_le_bytes(length) as usize];
+                    reader.read_exact(&mut body)?;
+                    let (seq, op) =
+                        decode_record(&body, &*self.codec).ok_or_else(|| invalid_data("malformed operation"))?;
+                    let mut state = lock(&self.state);
+                    if state.epoch.is_none() || seq != state.seq + 1 {
+                        return Err(invalid_data(format!("expected operation #{}, got #{}", state.seq + 1, seq)));
+                    }
+                    if !op.is_valid(state.list.len()) {
+                        return Err(invalid_data(format!("operation #{} is out of range", seq)));
+                    }
+                    op.apply(&mut state.list);
+                    state.seq = seq;
+                }
+                other => return Err(invalid_data(format!("unknown message {}", other))),
+            }
+        }
+    }
+
+    /// Connect to a leader over TCP and sync until the connection closes.
+    pub fn run_tcp(&self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        self.run(stream)
+    }
+
+    /// Connect to a leader over a Unix domain socket and sync until the connection closes.
+    #[cfg(unix)]
+    pub fn run_unix(&self, path: impl AsRef<std::path::Path>) -> io::Result<()> {
+        self.run(std::os::unix::net::UnixStream::connect(path)?)
+    }
+
+    /// A snapshot of the replicated list.
+    pub fn snapshot(&self) -> ChunkListSnapshot<T> {
+        lock(&self.state).list.snapshot()
+    }
+
+    /// Sequence number of the last applied operation.
+    pub fn seq(&self) -> u64 {
+        lock(&self.state).seq
+    }
+
+    /// Whether the replica has received a snapshot yet.
+    pub fn is_bootstrapped(&self) -> bool {
+        lock(&self.state).epoch.is_some()
+    }
+}
@@ -0,0 +1,108 @@
+use crate::execution::ExecutionConfig;
+use crate::ChunkList;
+use rayon::prelude::*;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Prefix scan of `chunks` in place, with the two-pass chunk algorithm: every chunk is
+/// scanned locally in parallel, a sequential pass turns the chunk totals into the carry
+/// each chunk starts from, then every chunk but the first adds its carry in parallel.
+///
+/// With an identity the scan is exclusive: each element becomes the combination of the
+/// elements before it.
+fn scan_chunks<T, F>(chunks: &mut [Arc<Vec<T>>], identity: Option<&T>, op: &F, execution: &ExecutionConfig)
+where T: Send + Sync + Clone, F: Fn(&T, &T) -> T + Sync, {
+    // Pass 1: local scans, keeping each chunk's total
+    let work = chunks.iter().map(|chunk| chunk.len()).sum();
+    let totals: Vec<Option<T>> = execution.run(work, |min_len| {
+        chunks
+            .par_iter_mut()
+            .with_min_len(min_len)
+            .map(|chunk| {
+                let mut acc = identity.cloned();
+                for item in Arc::make_mut(chunk).iter_mut() {
+                    let next = match &acc {
+                        Some(acc) => op(acc, item),
+                        None => item.clone(),
+                    };
+                    if identity.is_some() {
+                        *item = acc.replace(next).unwrap();
+                    } else {
+                        *item = next.clone();
+                        acc = Some(next);
+                    }
+                }
+                acc
+            })
+            .collect()
+    });
+
+    // Pass 2: the carry into every chunk is the combined total of the chunks before it
+    let mut carries = Vec::with_capacity(totals.len());
+    let mut running: Option<T> = None;
+    for total in totals {
+        carries.push(running.clone());
+        running = match (running, total) {
+            (Some(a), Some(b)) => Some(op(&a, &b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    // Pass 3: fix up every chunk with its carry
+    execution.run(work, |min_len| {
+        chunks.par_iter_mut().zip(carries).with_min_len(min_len).for_each(|(chunk, carry)| {
+            if let Some(carry) = carry {
+                for item in Arc::make_mut(chunk).iter_mut() {
+                    *item = op(&carry, item);
+                }
+            }
+        })
+    });
+}
+
+impl<T> ChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Helper: Scan a copy of the list (chunks are only copied as they're written).
+    fn scanned(&self, identity: Option<&T>, op: impl Fn(&T, &T) -> T + Sync) -> ChunkList<T> {
+        let mut list = ChunkList::new(self.chunk_size);
+        list.my_list = self.my_list.clone();
+        scan_chunks(&mut list.my_list, identity, &op, &self.execution);
+        list
+    }
+
+    /// Helper: Scan the list in place, reporting changed elements to observers.
+    fn scan_in_place(&mut self, identity: Option<&T>, op: impl Fn(&T, &T) -> T + Sync) {
+        let old = self.is_observed().then(|| self.my_list.clone());
+        scan_chunks(&mut self.my_list, identity, &op, &self.execution);
+        if let Some(old) = old {
+            self.report_updates(old);
+        }
+    }
+
+    /// Inclusive prefix scan in parallel: element `i` of the result combines elements
+    /// `0..=i` with `op`, which has to be associative. The result keeps the chunk layout.
+    pub fn scan_inclusive<F>(&self, op: F) -> ChunkList<T>
+    where F: Fn(&T, &T) -> T + Sync, {
+        self.scanned(None, op)
+    }
+
+    /// Inclusive prefix scan in place (see `scan_inclusive`).
+    pub fn scan_inclusive_in_place<F>(&mut self, op: F)
+    where F: Fn(&T, &T) -> T + Sync, {
+        self.scan_in_place(None, op)
+    }
+
+    /// Exclusive prefix scan in parallel: element `i` of the result combines `identity`
+    /// and elements `0..i` with `op`, which has to be associative with `identity` as its
+    /// identity. The result keeps the chunk layout.
+    pub fn scan_exclusive<F>(&self, identity: T, op: F) -> ChunkList<T>
+    where F: Fn(&T, &T) -> T + Sync, {
+        self.scanned(Some(&identity), op)
+    }
+
+    /// Exclusive prefix scan in place (see `scan_exclusive`).
+    pub fn scan_exclusive_in_place<F>(&mut self, identity: T, op: F)
+    where F: Fn(&T, &T) -> T + Sync, {
+        self.scan_in_place(Some(&identity), op)
+    }
+}
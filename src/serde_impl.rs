@@ -0,0 +1,102 @@
+use crate::ChunkList;
+use alloc::vec::Vec;
+use serde::de::{Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+use core::fmt::{self, Debug};
+use core::marker::PhantomData;
+
+/// Flat representation: a plain sequence of elements, independent of the chunking.
+impl<T> Serialize for ChunkList<T>
+where T: Serialize, {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = self.my_list.iter().map(|chunk| chunk.len()).sum();
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        for item in self.my_list.iter().flat_map(|chunk| chunk.iter()) {
+            seq.serialize_element(item)?;
+        }
+        seq.end()
+    }
+}
+
+/// Flat representation: elements are packed into full chunks of the default chunk size.
+impl<'de, T> Deserialize<'de> for ChunkList<T>
+where T: Deserialize<'de> + Ord + Debug + Send + Sync + Clone, {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FlatVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> Visitor<'de> for FlatVisitor<T>
+        where T: Deserialize<'de> + Ord + Debug + Send + Sync + Clone, {
+            type Value = ChunkList<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "a sequence of elements")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                // Don't trust the size hint too far for the allocation up front
+                let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+                while let Some(item) = seq.next_element()? {
+                    items.push(item);
+                }
+                let mut list = ChunkList::default();
+                list.refill(items);
+                Ok(list)
+            }
+        }
+
+        deserializer.deserialize_seq(FlatVisitor(PhantomData))
+    }
+}
+
+/// Layout-preserving representation, for use with `#[serde(with = "chunklist::serde_layout")]`.
+///
+/// The list is written as `{ chunk_size, chunks: [[...], ...] }`, so a deserialized list
+/// has the same chunk size, `chunk_amount()` and element layout as the original.
+pub mod layout {
+    use crate::ChunkList;
+    use serde::de::{self, Deserializer};
+    use serde::ser::Serializer;
+    use serde::{Deserialize, Serialize};
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::fmt::Debug;
+
+    #[derive(Serialize)]
+    #[serde(rename = "ChunkList")]
+    struct LayoutRef<'a, T> {
+        chunk_size: usize,
+        chunks: Vec<&'a [T]>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename = "ChunkList")]
+    struct Layout<T> {
+        chunk_size: usize,
+        chunks: Vec<Vec<T>>,
+    }
+
+    /// Serialize `list` with its chunk layout.
+    pub fn serialize<T, S>(list: &ChunkList<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Serialize, S: Serializer, {
+        LayoutRef {
+            chunk_size: list.chunk_size,
+            chunks: list.my_list.iter().map(|chunk| chunk.as_slice()).collect(),
+        }
+        .serialize(serializer)
+    }
+
+    /// Deserialize a list written by `serialize`, restoring its chunk layout.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<ChunkList<T>, D::Error>
+    where T: Deserialize<'de> + Ord + Debug + Send + Sync + Clone, D: Deserializer<'de>, {
+        let layout: Layout<T> = Layout::deserialize(deserializer)?;
+        if let Some(chunk) = layout.chunks.iter().find(|c| c.len() > layout.chunk_size.max(1)) {
+            return Err(de::Error::invalid_length(
+                chunk.len(),
+                &"chunks no longer than chunk_size",
+            ));
+        }
+        let mut list = ChunkList::new(layout.chunk_size);
+        list.my_list = layout.chunks.into_iter().map(Arc::new).collect();
+        Ok(list)
+    }
+}
@@ -0,0 +1,145 @@
+use crate::ChunkList;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A set of independent ChunkLists ("shards"), with every element routed to one shard
+/// by a key function. Threads working on different shards never contend with each other.
+pub struct ShardedChunkList<T> {
+    shards: Vec<RwLock<ChunkList<T>>>,
+    chunk_size: usize,
+    key: Box<dyn Fn(&T) -> u64 + Send + Sync>,
+}
+
+impl<T: Debug> Debug for ShardedChunkList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedChunkList")
+            .field("shards", &self.shards)
+            .field("chunk_size", &self.chunk_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> ShardedChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone + Hash, {
+    /// Creates a new ShardedChunkList that routes elements by their hash.
+    pub fn new(shard_count: usize, chunk_size: usize) -> Self {
+        Self::with_key(shard_count, chunk_size, |t: &T| {
+            // DefaultHasher::new() uses fixed keys, so routing is stable between runs
+            let mut hasher = DefaultHasher::new();
+            t.hash(&mut hasher);
+            hasher.finish()
+        })
+    }
+}
+
+impl<T> ShardedChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Creates a new ShardedChunkList that routes elements with a user key function.
+    /// Equal elements must produce equal keys.
+    pub fn with_key<F>(shard_count: usize, chunk_size: usize, key: F) -> Self
+    where F: Fn(&T) -> u64 + Send + Sync + 'static, {
+        assert!(shard_count > 0, "Shard count must be at least 1");
+        Self {
+            shards: (0..shard_count)
+                .map(|_| RwLock::new(ChunkList::new(chunk_size)))
+                .collect(),
+            chunk_size,
+            key: Box::new(key),
+        }
+    }
+
+    fn read_shard(&self, shard: usize) -> RwLockReadGuard<'_, ChunkList<T>> {
+        self.shards[shard].read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_shard(&self, shard: usize) -> RwLockWriteGuard<'_, ChunkList<T>> {
+        self.shards[shard].write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Index of the shard that `t` belongs to.
+    pub fn shard_of(&self, t: &T) -> usize {
+        ((self.key)(t) % self.shards.len() as u64) as usize
+    }
+
+    /// Number of shards.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Get current chunk size (shared by every shard)
+    pub fn get_chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Add an element to its shard.
+    pub fn add(&self, t: T) {
+        let shard = self.shard_of(&t);
+        self.write_shard(shard).add(t);
+    }
+
+    /// Remove the first occurrence of `t` from its shard.
+    pub fn remove(&self, t: &T) {
+        self.write_shard(self.shard_of(t)).remove(t);
+    }
+
+    /// Remove all instances of `t`. Only the shard `t` belongs to is touched.
+    pub fn remove_all(&self, t: &T) {
+        self.write_shard(self.shard_of(t)).remove_all(t);
+    }
+
+    /// Check if the list contains a given item. Only the shard `t` belongs to is searched.
+    pub fn contains(&self, t: &T) -> bool {
+        self.read_shard(self.shard_of(t)).contains(t)
+    }
+
+    /// Return the total number of elements across all shards.
+    pub fn len(&self) -> usize {
+        (0..self.shards.len()).map(|shard| self.read_shard(shard).len()).sum()
+    }
+
+    /// Check if every shard is empty.
+    pub fn is_empty(&self) -> bool {
+        (0..self.shards.len()).all(|shard| self.read_shard(shard).is_empty())
+    }
+
+    /// Clear every shard.
+    pub fn clear(&self) {
+        for shard in 0..self.shards.len() {
+            self.write_shard(shard).clear();
+        }
+    }
+
+    /// Run `f` with shared access to a single shard.
+    pub fn with_shard<R>(&self, shard: usize, f: impl FnOnce(&ChunkList<T>) -> R) -> R {
+        f(&self.read_shard(shard))
+    }
+
+    /// Return a new Vec containing all elements, shard by shard (not sorted).
+    pub fn get_list(&self) -> Vec<T> {
+        let mut items = Vec::new();
+        for shard in 0..self.shards.len() {
+            items.extend(self.read_shard(shard).get_list());
+        }
+        items
+    }
+
+    /// Build a merged, sorted ChunkList of every element across all shards.
+    pub fn sorted(&self) -> ChunkList<T> {
+        // One sort over everything, then the elements are packed into full chunks
+        let mut merged = ChunkList::new(self.chunk_size);
+        let mut items = self.get_list();
+        merged.sort_items(&mut items);
+        merged.refill(items);
+        merged
+    }
+
+    /// Unwrap into the individual shards.
+    pub fn into_shards(self) -> Vec<ChunkList<T>> {
+        self.shards
+            .into_iter()
+            .map(|shard| shard.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .collect()
+    }
+}
@@ -0,0 +1,87 @@
+use crate::ChunkList;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// A read-only, point-in-time view of a ChunkList.
+///
+/// A snapshot shares the list's chunks through `Arc`, so taking one only copies
+/// chunk pointers. Writers copy a chunk the first time they modify it while a
+/// snapshot still holds it, so a long scan never blocks them and never sees their changes.
+#[derive(Debug, Clone)]
+pub struct ChunkListSnapshot<T> {
+    chunks: Vec<Arc<Vec<T>>>,
+    chunk_size: usize,
+}
+
+impl<T> ChunkListSnapshot<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    pub(crate) fn new(chunks: Vec<Arc<Vec<T>>>, chunk_size: usize) -> Self {
+        Self { chunks, chunk_size }
+    }
+
+    /// Get an item at a particular index.
+    pub fn get(&self, index: usize) -> &T {
+        let mut remaining = index;
+        for chunk in &self.chunks {
+            if remaining < chunk.len() {
+                return &chunk[remaining];
+            }
+            remaining -= chunk.len();
+        }
+        panic!("Index out of range");
+    }
+
+    /// Iterate over all elements in order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.chunks.iter().flat_map(|chunk| chunk.iter())
+    }
+
+    /// Iterate over the chunks in order.
+    pub fn chunks(&self) -> impl Iterator<Item = &[T]> {
+        self.chunks.iter().map(|chunk| chunk.as_slice())
+    }
+
+    /// Check if the snapshot contains a given item, in parallel.
+    pub fn contains(&self, t: &T) -> bool {
+        #[cfg(feature = "rayon")]
+        let found = self.chunks.par_iter().any(|chunk| chunk.contains(t));
+        #[cfg(not(feature = "rayon"))]
+        let found = self.chunks.iter().any(|chunk| chunk.contains(t));
+        found
+    }
+
+    /// Return a new Vec containing all elements (in order).
+    pub fn get_list(&self) -> Vec<T> {
+        self.iter().cloned().collect()
+    }
+
+    /// Return the total number of elements.
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.len()).sum()
+    }
+
+    /// Check if the snapshot is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the chunk size of the list at the time of the snapshot
+    pub fn get_chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Get amount of chunks in the snapshot
+    pub fn chunk_amount(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Turn the snapshot back into a mutable ChunkList, still sharing unchanged chunks.
+    pub fn to_chunk_list(&self) -> ChunkList<T> {
+        let mut list = ChunkList::new(self.chunk_size);
+        list.my_list = self.chunks.clone();
+        list
+    }
+}
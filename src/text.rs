@@ -0,0 +1,290 @@
+use crate::ChunkList;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::fmt::{self, Debug, Display};
+use std::io::{self, BufRead, Write};
+
+/// Error importing text.
+#[derive(Debug)]
+pub enum TextError {
+    Io(io::Error),
+    /// A record couldn't be parsed; `line` is the 1-based line it starts on.
+    InvalidRecord { line: usize, reason: String },
+}
+
+impl Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextError::Io(e) => write!(f, "I/O error: {}", e),
+            TextError::InvalidRecord { line, reason } => write!(f, "line {}: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for TextError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TextError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TextError {
+    fn from(e: io::Error) -> Self {
+        TextError::Io(e)
+    }
+}
+
+/// A raw record and the line it starts on.
+type Record = (usize, String);
+
+/// Reads records (lines, or CSV records that may span lines) in chunk-sized blocks.
+struct Blocks<R> {
+    reader: R,
+    line: usize,
+    csv: bool,
+}
+
+impl<R: BufRead> Blocks<R> {
+    /// Read the next record, without its line ending.
+    fn next_record(&mut self) -> io::Result<Option<Record>> {
+        let start = self.line + 1;
+        let mut record = String::new();
+        // Whether the record so far ends inside a quoted CSV field
+        let mut quoted = false;
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                if record.is_empty() {
+                    return Ok(None);
+                }
+                // Unterminated quote at the end of the input; the parser reports it
+                return Ok(Some((start, record)));
+            }
+            self.line += 1;
+            // In CSV, a line ending inside quotes is part of the field
+            if self.csv && !line.matches('"').count().is_multiple_of(2) {
+                quoted = !quoted;
+            }
+            record.push_str(&line);
+            if !quoted {
+                break;
+            }
+        }
+        let trimmed = record.trim_end_matches(['\n', '\r']).len();
+        record.truncate(trimmed);
+        Ok(Some((start, record)))
+    }
+
+    /// Read up to `blocks` blocks of `block_len` records.
+    fn next_blocks(&mut self, blocks: usize, block_len: usize) -> io::Result<Vec<Vec<Record>>> {
+        let mut result = Vec::new();
+        'blocks: for _ in 0..blocks {
+            let mut block = Vec::with_capacity(block_len);
+            while block.len() < block_len {
+                match self.next_record()? {
+                    Some(record) => block.push(record),
+                    None => {
+                        if !block.is_empty() {
+                            result.push(block);
+                        }
+                        break 'blocks;
+                    }
+                }
+            }
+            result.push(block);
+        }
+        Ok(result)
+    }
+}
+
+/// Split a CSV record into fields (RFC 4180: fields may be quoted, `""` is a quote).
+fn split_csv(record: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = record.chars().peekable();
+    let mut quoted = false;
+    let mut at_field_start = true;
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', false) if at_field_start => quoted = true,
+            ('"', false) => return Err("unexpected quote in unquoted field".to_string()),
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => {
+                quoted = false;
+                if !matches!(chars.peek(), None | Some(',')) {
+                    return Err("unexpected character after closing quote".to_string());
+                }
+            }
+            (',', false) => {
+                fields.push(std::mem::take(&mut field));
+                at_field_start = true;
+                continue;
+            }
+            (c, _) => field.push(c),
+        }
+        at_field_start = false;
+    }
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// Quote a CSV field if it needs it.
+fn write_csv_field(out: &mut String, field: &str) {
+    if field.contains([',', '"', '\n', '\r']) {
+        out.push('"');
+        out.push_str(&field.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(field);
+    }
+}
+
+impl<T> ChunkList<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    /// Append the records of `blocks`, parsing one chunk-sized block per task. Nothing is
+    /// appended if any record fails to parse.
+    fn extend_from_blocks<R, F>(&mut self, mut blocks: Blocks<R>, parse: F) -> Result<(), TextError>
+    where R: BufRead, F: Fn(&str) -> Result<T, String> + Sync, {
+        let block_len = self.chunk_size.max(1);
+        #[cfg(feature = "rayon")]
+        let threads = self.execution.threads();
+        #[cfg(not(feature = "rayon"))]
+        let threads = 1;
+        let parse_block = |block: Vec<Record>| -> Result<Vec<T>, TextError> {
+            block
+                .into_iter()
+                .map(|(line, record)| parse(&record).map_err(|reason| TextError::InvalidRecord { line, reason }))
+                .collect()
+        };
+        let mut chunks = Vec::new();
+        loop {
+            // Read one block per thread, then parse them all at once
+            let batch = blocks.next_blocks(threads, block_len)?;
+            if batch.is_empty() {
+                self.append_chunks(chunks);
+                return Ok(());
+            }
+            #[cfg(feature = "rayon")]
+            let parsed: Vec<Result<Vec<T>, TextError>> = {
+                let work = batch.iter().map(Vec::len).sum();
+                self.execution.run(work, |min_len| {
+                    batch.into_par_iter().with_min_len(min_len).map(parse_block).collect()
+                })
+            };
+            #[cfg(not(feature = "rayon"))]
+            let parsed: Vec<Result<Vec<T>, TextError>> = batch.into_iter().map(parse_block).collect();
+            // Report the first bad record in input order, however the blocks were scheduled
+            for chunk in parsed {
+                chunks.push(chunk?);
+            }
+        }
+    }
+
+    /// Build a list with one element per line of `reader`, parsed by `parse`.
+    ///
+    /// Lines are read in chunk-sized blocks that are parsed in parallel, each becoming one
+    /// chunk. A parse error is reported with its 1-based line number.
+    pub fn from_reader_lines<R, F, E>(reader: R, chunk_size: usize, parse: F) -> Result<Self, TextError>
+    where R: BufRead, F: Fn(&str) -> Result<T, E> + Sync, E: Display, {
+        let mut list = ChunkList::new(chunk_size);
+        list.extend_from_reader_lines(reader, parse)?;
+        Ok(list)
+    }
+
+    /// Append one element per line of `reader`, like `from_reader_lines`, parsing on this
+    /// list's execution config. Nothing is appended if a line fails to parse.
+    pub fn extend_from_reader_lines<R, F, E>(&mut self, reader: R, parse: F) -> Result<(), TextError>
+    where R: BufRead, F: Fn(&str) -> Result<T, E> + Sync, E: Display, {
+        let blocks = Blocks { reader, line: 0, csv: false };
+        self.extend_from_blocks(blocks, |line| parse(line).map_err(|e| e.to_string()))
+    }
+
+    /// Build a list with one element per CSV record of `reader`, parsed from its fields by
+    /// `parse`. Quoted fields may contain commas, quotes (`""`) and line breaks. With
+    /// `has_header`, the first record is skipped.
+    ///
+    /// Records are parsed in parallel in chunk-sized blocks, like `from_reader_lines`.
+    pub fn from_csv<R, F, E>(reader: R, chunk_size: usize, has_header: bool, parse: F) -> Result<Self, TextError>
+    where R: BufRead, F: Fn(&[String]) -> Result<T, E> + Sync, E: Display, {
+        let mut list = ChunkList::new(chunk_size);
+        list.extend_from_csv(reader, has_header, parse)?;
+        Ok(list)
+    }
+
+    /// Append one element per CSV record of `reader`, like `from_csv`, parsing on this
+    /// list's execution config. Nothing is appended if a record fails to parse.
+    pub fn extend_from_csv<R, F, E>(&mut self, reader: R, has_header: bool, parse: F) -> Result<(), TextError>
+    where R: BufRead, F: Fn(&[String]) -> Result<T, E> + Sync, E: Display, {
+        let mut blocks = Blocks { reader, line: 0, csv: true };
+        if has_header {
+            if let Some((line, header)) = blocks.next_record()? {
+                split_csv(&header).map_err(|reason| TextError::InvalidRecord { line, reason })?;
+            }
+        }
+        self.extend_from_blocks(blocks, |record| {
+            let fields = split_csv(record)?;
+            parse(&fields).map_err(|e| e.to_string())
+        })
+    }
+
+    /// Write every element on its own line, formatted by `format`, one chunk at a time.
+    pub fn write_lines_with<W, F>(&self, mut w: W, format: F) -> io::Result<()>
+    where W: Write, F: Fn(&T, &mut String), {
+        let mut buffer = String::new();
+        for chunk in &self.my_list {
+            buffer.clear();
+            for item in chunk.iter() {
+                format(item, &mut buffer);
+                buffer.push('\n');
+            }
+            w.write_all(buffer.as_bytes())?;
+        }
+        w.flush()
+    }
+
+    /// Write every element on its own line, one chunk at a time.
+    pub fn write_lines<W: Write>(&self, w: W) -> io::Result<()>
+    where T: Display, {
+        use std::fmt::Write as _;
+        self.write_lines_with(w, |item, out| {
+            let _ = write!(out, "{}", item);
+        })
+    }
+
+    /// Write every element as a CSV record of the fields returned by `fields`, one chunk
+    /// at a time, after an optional header record. Fields are quoted when needed.
+    pub fn write_csv<W, F, S>(&self, mut w: W, header: Option<&[&str]>, fields: F) -> io::Result<()>
+    where W: Write, F: Fn(&T) -> Vec<S>, S: AsRef<str>, {
+        let write_record = |out: &mut String, fields: &mut dyn Iterator<Item = &str>| {
+            for (i, field) in fields.enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_csv_field(out, field);
+            }
+            out.push_str("\r\n");
+        };
+        let mut buffer = String::new();
+        if let Some(header) = header {
+            write_record(&mut buffer, &mut header.iter().copied());
+            w.write_all(buffer.as_bytes())?;
+        }
+        for chunk in &self.my_list {
+            buffer.clear();
+            for item in chunk.iter() {
+                let values = fields(item);
+                write_record(&mut buffer, &mut values.iter().map(|field| field.as_ref()));
+            }
+            w.write_all(buffer.as_bytes())?;
+        }
+        w.flush()
+    }
+}
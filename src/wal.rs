@@ -0,0 +1,379 @@
+use crate::format::{crc32, ChunkCodec, DefaultCodec, FormatError};
+use crate::ChunkList;
+use std::fmt::Debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+const WAL_FILE: &str = "wal.log";
+const CHECKPOINT_TMP: &str = "checkpoint.tmp";
+
+// Log record header: body length (u32), CRC-32 of the body (u32)
+const RECORD_HEADER_LEN: usize = 8;
+
+const OP_ADD: u8 = 1;
+const OP_SET: u8 = 2;
+const OP_REMOVE_AT: u8 = 3;
+const OP_REMOVE_ALL: u8 = 4;
+const OP_SORT: u8 = 5;
+
+/// A ChunkList that survives crashes.
+///
+/// Every mutation is appended to a write-ahead log in a directory (and synced) before it
+/// is applied. Every `checkpoint_interval` records, the list is written to a checkpoint
+/// in the native format and the log is emptied. `open` loads the latest checkpoint and
+/// replays the log records after it; a torn record at the end of the log, left by a
+/// crash in the middle of a write, is truncated away.
+///
+/// Records and checkpoints carry sequence numbers, so a crash between writing a
+/// checkpoint and emptying the log doesn't apply any record twice, and a log that
+/// skips a record is rejected instead of being replayed with a hole in it.
+///
+/// A failed automatic checkpoint doesn't fail the operation that triggered it, since
+/// that operation is already durable. It's retried on the next operation and reported
+/// through `checkpoint_error` until a checkpoint succeeds.
+///
+/// A record that fails to be written or synced is truncated away again and the list is
+/// left unchanged. If even that fails, the log is in an unknown state and the list
+/// refuses further writes until it's reopened.
+#[derive(Debug)]
+pub struct DurableChunkList<T, C> {
+    list: ChunkList<T>,
+    codec: C,
+    dir: PathBuf,
+    wal: File,
+    // Length of the log up to the last record that was fully written and synced
+    wal_len: u64,
+    // Set when a failed record couldn't be rolled back
+    poisoned: bool,
+    // Sequence number of the last applied record
+    seq: u64,
+    records_since_checkpoint: usize,
+    checkpoint_interval: usize,
+    // Why the last automatic checkpoint failed, cleared by a successful checkpoint
+    checkpoint_error: Option<FormatError>,
+}
+
+fn checkpoint_name(seq: u64) -> String {
+    format!("checkpoint-{:020}.chkl", seq)
+}
+
+/// Checkpoints in `dir`, ordered by sequence number.
+fn checkpoints(dir: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+    let mut found = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+        if let Some(seq) = name.strip_prefix("checkpoint-").and_then(|rest| rest.strip_suffix(".chkl")) {
+            if let Ok(seq) = seq.parse() {
+                found.push((seq, path));
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+fn sync_dir(dir: &Path) {
+    // Persists renames on Unix; directories can't be opened like this everywhere
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+impl<T> DurableChunkList<T, T::Codec>
+where T: Ord + Debug + Send + Sync + Clone + DefaultCodec, T::Codec: Sync, {
+    /// Open (or create) a durable list in `dir` with the default codec.
+    ///
+    /// `chunk_size` is only used when `dir` holds no checkpoint yet.
+    pub fn open(dir: impl AsRef<Path>, chunk_size: usize) -> Result<Self, FormatError> {
+        Self::open_with(dir, chunk_size, T::Codec::default())
+    }
+}
+
+impl<T, C> DurableChunkList<T, C>
+where T: Ord + Debug + Send + Sync + Clone, C: ChunkCodec<T> + Sync, {
+    /// Open (or create) a durable list in `dir`, encoding elements with `codec`.
+    pub fn open_with(dir: impl AsRef<Path>, chunk_size: usize, codec: C) -> Result<Self, FormatError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        // A checkpoint that was never renamed into place is incomplete
+        let _ = fs::remove_file(dir.join(CHECKPOINT_TMP));
+
+        let (mut list, mut seq) = match checkpoints(&dir)?.pop() {
+            Some((seq, path)) => (ChunkList::load_with(path, &codec)?, seq),
+            None => (ChunkList::new(chunk_size), 0),
+        };
+
+        // Replay the log, stopping at the first torn or corrupt record
+        let wal_path = dir.join(WAL_FILE);
+        let mut valid_len = 0u64;
+        let mut records = 0;
+        if let Ok(file) = File::open(&wal_path) {
+            let mut reader = BufReader::new(file);
+            while let Some(body) = read_record(&mut reader)? {
+                let Some((record_seq, op)) = decode_record(&body, &codec) else { break };
+                if record_seq > seq {
+                    if record_seq != seq + 1 {
+                        return Err(FormatError::Io(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("write-ahead log skips from record {} to {}", seq, record_seq),
+                        )));
+                    }
+                    if !op.is_valid(list.len()) {
+                        break;
+                    }
+                    op.apply(&mut list);
+                    seq = record_seq;
+                    records += 1;
+                }
+                valid_len += (RECORD_HEADER_LEN + body.len()) as u64;
+            }
+        }
+        let wal = OpenOptions::new().create(true).append(true).open(&wal_path)?;
+        if wal.metadata()?.len() > valid_len {
+            wal.set_len(valid_len)?;
+            wal.sync_all()?;
+        }
+
+        Ok(Self {
+            list,
+            codec,
+            dir,
+            wal,
+            wal_len: valid_len,
+            poisoned: false,
+            seq,
+            records_since_checkpoint: records,
+            checkpoint_interval: 10_000,
+            checkpoint_error: None,
+        })
+    }
+
+    /// Append a record to the log and sync it, then apply it.
+    fn log(&mut self, op: Op<T>) -> Result<(), FormatError> {
+        if self.poisoned {
+            return Err(FormatError::Io(io::Error::other("write-ahead log is in an unknown state after a failed write")));
+        }
+        let body = encode_record(self.seq + 1, &op, &self.codec);
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN + body.len());
+        record.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        record.extend_from_slice(&crc32(&body).to_le_bytes());
+        record.extend_from_slice(&body);
+        if let Err(e) = self.wal.write_all(&record).and_then(|_| self.wal.sync_data()) {
+            // Drop whatever part of the record made it, so a later record can't end up
+            // behind a torn one or share its sequence number
+            if self.wal.set_len(self.wal_len).and_then(|_| self.wal.sync_all()).is_err() {
+                self.poisoned = true;
+            }
+            return Err(e.into());
+        }
+
+        self.wal_len += record.len() as u64;
+        self.seq += 1;
+        op.apply(&mut self.list);
+        self.records_since_checkpoint += 1;
+        if self.records_since_checkpoint >= self.checkpoint_interval {
+            // The op is already durable; a failed checkpoint is retried on the next op
+            if let Err(e) = self.checkpoint() {
+                self.checkpoint_error = Some(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the list to a new checkpoint and empty the log.
+    pub fn checkpoint(&mut self) -> Result<(), FormatError> {
+        self.write_checkpoint()?;
+        self.checkpoint_error = None;
+        Ok(())
+    }
+
+    fn write_checkpoint(&mut self) -> Result<(), FormatError> {
+        let tmp = self.dir.join(CHECKPOINT_TMP);
+        let file = File::create(&tmp)?;
+        let mut writer = BufWriter::new(file);
+        self.list.write_with(&mut writer, &self.codec)?;
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&tmp, self.dir.join(checkpoint_name(self.seq)))?;
+        sync_dir(&self.dir);
+
+        // The new checkpoint covers everything before it
+        self.wal.set_len(0)?;
+        self.wal_len = 0;
+        self.wal.sync_all()?;
+        for (seq, path) in checkpoints(&self.dir)? {
+            if seq < self.seq {
+                fs::remove_file(path)?;
+            }
+        }
+        self.records_since_checkpoint = 0;
+        Ok(())
+    }
+
+    /// Set how many log records trigger an automatic checkpoint.
+    pub fn set_checkpoint_interval(&mut self, records: usize) {
+        self.checkpoint_interval = records.max(1);
+    }
+
+    /// Get how many log records trigger an automatic checkpoint
+    pub fn get_checkpoint_interval(&self) -> usize {
+        self.checkpoint_interval
+    }
+
+    /// Why the last automatic checkpoint failed, if no checkpoint succeeded since.
+    pub fn checkpoint_error(&self) -> Option<&FormatError> {
+        self.checkpoint_error.as_ref()
+    }
+
+    /// Number of records logged since the last checkpoint.
+    pub fn records_since_checkpoint(&self) -> usize {
+        self.records_since_checkpoint
+    }
+
+    /// The current list, for reading.
+    pub fn list(&self) -> &ChunkList<T> {
+        &self.list
+    }
+
+    /// Take the current list, leaving the files in place.
+    pub fn into_inner(self) -> ChunkList<T> {
+        self.list
+    }
+
+    /// Durably add an item, see `ChunkList::add`.
+    pub fn add(&mut self, t: T) -> Result<(), FormatError> {
+        self.log(Op::Add(t))
+    }
+
+    /// Durably replace the item at a particular index.
+    pub fn set(&mut self, index: usize, t: T) -> Result<(), FormatError> {
+        if index >= self.list.len() {
+            panic!("Index out of range");
+        }
+        self.log(Op::Set(index, t))
+    }
+
+    /// Durably remove and return the item at a particular index.
+    pub fn remove_at(&mut self, index: usize) -> Result<T, FormatError> {
+        let item = self.list.get(index).clone();
+        self.log(Op::RemoveAt(index))?;
+        Ok(item)
+    }
+
+    /// Durably remove all occurrences of an item.
+    pub fn remove_all(&mut self, t: &T) -> Result<(), FormatError> {
+        self.log(Op::RemoveAll(t.clone()))
+    }
+
+    /// Durably sort the list.
+    pub fn sort(&mut self) -> Result<(), FormatError> {
+        self.log(Op::Sort)
+    }
+}
+
+/// A logged mutation, shared with the replication protocol.
+pub(crate) enum Op<T> {
+    Add(T),
+    Set(usize, T),
+    RemoveAt(usize),
+    RemoveAll(T),
+    Sort,
+}
+
+impl<T> Op<T>
+where T: Ord + Debug + Send + Sync + Clone, {
+    pub(crate) fn encode<C: ChunkCodec<T>>(&self, codec: &C, out: &mut Vec<u8>) {
+        match self {
+            Op::Add(t) => {
+                out.push(OP_ADD);
+                codec.encode(t, out);
+            }
+            Op::Set(index, t) => {
+                out.push(OP_SET);
+                out.extend_from_slice(&(*index as u64).to_le_bytes());
+                codec.encode(t, out);
+            }
+            Op::RemoveAt(index) => {
+                out.push(OP_REMOVE_AT);
+                out.extend_from_slice(&(*index as u64).to_le_bytes());
+            }
+            Op::RemoveAll(t) => {
+                out.push(OP_REMOVE_ALL);
+                codec.encode(t, out);
+            }
+            Op::Sort => out.push(OP_SORT),
+        }
+    }
+
+    /// Whether the op can be applied to a list of `len` elements without panicking.
+    pub(crate) fn is_valid(&self, len: usize) -> bool {
+        match self {
+            Op::Set(index, _) | Op::RemoveAt(index) => *index < len,
+            _ => true,
+        }
+    }
+
+    pub(crate) fn apply(self, list: &mut ChunkList<T>) {
+        match self {
+            Op::Add(t) => list.add(t),
+            Op::Set(index, t) => list.set(index, t),
+            Op::RemoveAt(index) => {
+                list.remove_at(index);
+            }
+            Op::RemoveAll(t) => list.remove_all(&t),
+            Op::Sort => list.sort(),
+        }
+    }
+}
+
+/// Read one record body, or `None` at the end of the log or a torn record.
+fn read_record(r: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut header = [0; RECORD_HEADER_LEN];
+    if let Err(e) = r.read_exact(&mut header) {
+        return match e.kind() {
+            io::ErrorKind::UnexpectedEof => Ok(None),
+            _ => Err(e),
+        };
+    }
+    let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as u64;
+    let crc = u32::from_le_bytes(header[4..].try_into().unwrap());
+    let mut body = Vec::new();
+    r.take(len).read_to_end(&mut body)?;
+    if body.len() as u64 != len || crc32(&body) != crc {
+        return Ok(None);
+    }
+    Ok(Some(body))
+}
+
+/// Encode a record body: the sequence number followed by the op.
+pub(crate) fn encode_record<T, C>(seq: u64, op: &Op<T>, codec: &C) -> Vec<u8>
+where T: Ord + Debug + Send + Sync + Clone, C: ChunkCodec<T>, {
+    let mut body = Vec::new();
+    body.extend_from_slice(&seq.to_le_bytes());
+    op.encode(codec, &mut body);
+    body
+}
+
+/// Decode a record body into its sequence number and op.
+pub(crate) fn decode_record<T, C: ChunkCodec<T>>(body: &[u8], codec: &C) -> Option<(u64, Op<T>)> {
+    fn read_u64(input: &mut &[u8]) -> Option<u64> {
+        let (head, tail) = input.split_first_chunk::<8>()?;
+        *input = tail;
+        Some(u64::from_le_bytes(*head))
+    }
+
+    let mut input = body;
+    let seq = read_u64(&mut input)?;
+    let (&tag, rest) = input.split_first()?;
+    input = rest;
+    let op = match tag {
+        OP_ADD => Op::Add(codec.decode(&mut input).ok()?),
+        OP_SET => Op::Set(read_u64(&mut input)? as usize, codec.decode(&mut input).ok()?),
+        OP_REMOVE_AT => Op::RemoveAt(read_u64(&mut input)? as usize),
+        OP_REMOVE_ALL => Op::RemoveAll(codec.decode(&mut input).ok()?),
+        OP_SORT => Op::Sort,
+        _ => return None,
+    };
+    input.is_empty().then_some((seq, op))
+}
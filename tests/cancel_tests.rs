@@ -0,0 +1,86 @@
+#![cfg(feature = "rayon")]
+use chunklist::{CancellationToken, Cancelled, ChunkList, Progress};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+fn shuffled(len: u32, chunk_size: usize) -> ChunkList<u32> {
+    let mut list = ChunkList::new(chunk_size);
+    for i in 0..len {
+        list.add(i.wrapping_mul(2_654_435_761) % 10_007);
+    }
+    list
+}
+
+fn layout(list: &ChunkList<u32>) -> Vec<Vec<u32>> {
+    list.snapshot().chunks().map(|chunk| chunk.to_vec()).collect()
+}
+
+#[test]
+fn cancel_operations_report_progress() {
+    let token = CancellationToken::new();
+    let mut list = shuffled(10_000, 100);
+    let mut expected = list.get_list();
+    expected.sort();
+
+    // Sorting: one pass over the chunks, then 7 merge passes for 100 chunks
+    let last = Mutex::new(Vec::new());
+    list.sort_cancellable(&token, |p: Progress| last.lock().unwrap().push(p)).unwrap();
+    assert_eq!(list.get_list(), expected);
+    let reports = last.into_inner().unwrap();
+    assert!(reports.iter().all(|p| p.total == 800 && p.done <= 800));
+    assert_eq!(reports.iter().map(|p| p.done).max(), Some(800));
+
+    let calls = AtomicUsize::new(0);
+    list.set_chunk_size_cancellable(30, &token, |_| {
+        calls.fetch_add(1, Ordering::Relaxed);
+    })
+    .unwrap();
+    assert_eq!(calls.load(Ordering::Relaxed), 100);
+    assert_eq!(list.get_chunk_size(), 30);
+    assert_eq!(list.get_list(), expected);
+
+    list.remove_all_cancellable(&expected[0], &token, |_| {}).unwrap();
+    assert!(!list.contains(&expected[0]));
+    let doubled = list.par_map_cancellable(|x| *x as u64 * 2, &token, |_| {}).unwrap();
+    assert_eq!(*doubled.get(0), *list.get(0) as u64 * 2);
+    let evens = list.par_filter_cancellable(|x| x % 2 == 0, &token, |_| {}).unwrap();
+    assert_eq!(evens.len(), list.count_if(|x| x % 2 == 0));
+}
+
+#[test]
+fn cancel_leaves_list_unchanged() {
+    let mut list = shuffled(100_000, 50);
+    let before = layout(&list);
+
+    // Cancel from the progress callback, a few chunks in
+    let cancel_after = |token: &CancellationToken, chunks: usize| {
+        let token = token.clone();
+        move |p: Progress| {
+            if p.done >= chunks {
+                token.cancel();
+            }
+        }
+    };
+    let token = CancellationToken::new();
+    assert_eq!(list.sort_cancellable(&token, cancel_after(&token, 2_100)), Err(Cancelled));
+    assert_eq!(layout(&list), before);
+
+    let token = CancellationToken::new();
+    assert_eq!(list.set_chunk_size_cancellable(10, &token, cancel_after(&token, 5)), Err(Cancelled));
+    assert_eq!(list.get_chunk_size(), 50);
+
+    let token = CancellationToken::new();
+    let result = list.par_for_each_chunk_cancellable(|_, chunk| chunk.iter_mut().for_each(|x| *x = 0), &token, cancel_after(&token, 5));
+    assert_eq!(result, Err(Cancelled));
+    let token = CancellationToken::new();
+    assert_eq!(list.remove_all_cancellable(&before[0][0], &token, cancel_after(&token, 5)), Err(Cancelled));
+    assert_eq!(layout(&list), before);
+
+    // An already cancelled token stops before any work
+    let calls = AtomicUsize::new(0);
+    let result = list.par_map_cancellable(|x| *x, &token, |_| {
+        calls.fetch_add(1, Ordering::Relaxed);
+    });
+    assert_eq!(result.unwrap_err().to_string(), "operation cancelled");
+    assert_eq!(calls.load(Ordering::Relaxed), 0);
+}
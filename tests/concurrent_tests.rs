@@ -0,0 +1,174 @@
+#![cfg(feature = "std")]
+use chunklist::{ChunkList, ConcurrentChunkList, TransactionError};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn chunklist_insert_splits_chunks() {
+    let mut list = ChunkList::new(4);
+    for i in 0..8 {
+        list.add(i);
+    }
+    assert_eq!(list.chunk_amount(), 2);
+
+    // Inserting into a full chunk splits it in half
+    list.insert(1, 100);
+    assert_eq!(list.chunk_amount(), 3);
+    assert_eq!(list.get_list(), vec![0, 100, 1, 2, 3, 4, 5, 6, 7]);
+
+    // Indices are positional even though the chunks are no longer full
+    assert_eq!(*list.get(4), 3);
+    assert_eq!(list.remove_at(4), 3);
+    list.set(4, 40);
+    assert_eq!(list.get_list(), vec![0, 100, 1, 2, 40, 5, 6, 7]);
+}
+
+#[test]
+fn transaction_commits_atomically() {
+    let list = Arc::new(ConcurrentChunkList::new(8));
+    for _ in 0..64 {
+        list.add(1);
+    }
+
+    // Writers keep moving one unit between two positions; readers must never see
+    // the intermediate state where the total differs from 64.
+    let writer = {
+        let list = Arc::clone(&list);
+        thread::spawn(move || {
+            for i in 0..500 {
+                let from = i % 64;
+                let to = (i * 7 + 3) % 64;
+                let mut tx = list.transaction();
+                tx.modify(from, |a| *a -= 1).modify(to, |b| *b += 1);
+                tx.commit().unwrap();
+            }
+        })
+    };
+    let reader = {
+        let list = Arc::clone(&list);
+        thread::spawn(move || {
+            for _ in 0..500 {
+                let total: i32 = list.get_list().iter().sum();
+                assert_eq!(total, 64);
+            }
+        })
+    };
+    writer.join().unwrap();
+    reader.join().unwrap();
+    assert_eq!(list.get_list().iter().sum::<i32>(), 64);
+}
+
+#[test]
+fn concurrent_transactions_lose_no_updates() {
+    let list = Arc::new(ConcurrentChunkList::new(4));
+    for i in 0..32 {
+        list.add(i * 10);
+    }
+
+    // Two writers increment and move items at the same time; every increment must land
+    let writers: Vec<_> = (0..2)
+        .map(|w| {
+            let list = Arc::clone(&list);
+            thread::spawn(move || {
+                for i in 0..500 {
+                    let mut tx = list.transaction();
+                    tx.modify((i + w * 5) % 32, |x| *x += 1).move_item(i % 32, (i * 3 + w) % 32);
+                    tx.commit().unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    let total: i32 = list.get_list().iter().sum();
+    assert_eq!(total, (0..32).map(|i| i * 10).sum::<i32>() + 1000);
+
+    // Reads resolve under the same locks as the writes of the transaction
+    let mut tx = list.transaction();
+    let before = tx.get(0);
+    tx.modify(0, |x| *x = -5);
+    let after = tx.get(0);
+    assert_eq!(before.get(), None);
+    tx.commit().unwrap();
+    assert_eq!(after.get(), Some(&-5));
+    assert_ne!(before.get(), Some(&-5));
+    assert_eq!(list.get(0), -5);
+}
+
+#[test]
+fn transactions_on_other_chunks_do_not_wait() {
+    let list = Arc::new(ConcurrentChunkList::new(4));
+    for i in 0..16 {
+        list.add(i);
+    }
+
+    // The first transaction holds chunk 0 until the second one, on the last chunk,
+    // has committed. Locking the chunk prefix from chunk 0 would deadlock here.
+    let (done, wait) = mpsc::channel();
+    let other = {
+        let list = Arc::clone(&list);
+        thread::spawn(move || {
+            let mut tx = list.transaction();
+            tx.modify(0, move |x| {
+                wait.recv_timeout(Duration::from_secs(10)).expect("the other commit was blocked");
+                *x += 100;
+            });
+            tx.commit().unwrap();
+        })
+    };
+    thread::sleep(Duration::from_millis(50));
+    let mut tx = list.transaction();
+    tx.set(13, 130);
+    tx.commit().unwrap();
+    done.send(()).unwrap();
+    other.join().unwrap();
+    assert_eq!(list.get(0), 100);
+    assert_eq!(list.get(13), 130);
+}
+
+#[test]
+fn transaction_rolls_back_on_error_and_panic() {
+    let list = ConcurrentChunkList::new(3);
+    for i in 0..6 {
+        list.add(i);
+    }
+
+    // The second operation is out of range, so the first one must not be applied either
+    let mut tx = list.transaction();
+    tx.set(0, 10).remove_at(6);
+    assert_eq!(
+        tx.commit(),
+        Err(TransactionError::IndexOutOfRange { op: 1, index: 6, len: 6 })
+    );
+    assert_eq!(list.get_list(), vec![0, 1, 2, 3, 4, 5]);
+
+    // Panicking while building the transaction discards it
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut tx = list.transaction();
+        tx.set(1, 11);
+        panic!("user code failed");
+    }));
+    assert!(result.is_err());
+    assert_eq!(list.get_list(), vec![0, 1, 2, 3, 4, 5]);
+
+    // So does a `modify` closure panicking during commit
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut tx = list.transaction();
+        tx.insert(0, -1).modify(2, |_| panic!("user code failed"));
+        tx.commit()
+    }));
+    assert!(result.is_err());
+    assert_eq!(list.get_list(), vec![0, 1, 2, 3, 4, 5]);
+
+    // Inserts that overflow a chunk go through the layout-changing path
+    let mut tx = list.transaction();
+    tx.insert(0, -1).insert(7, 6).remove_at(3);
+    tx.commit().unwrap();
+    assert_eq!(list.get_list(), vec![-1, 0, 1, 3, 4, 5, 6]);
+    assert!(list.chunk_amount() > 2);
+
+    let plain = list.into_chunk_list();
+    assert_eq!(plain.len(), 7);
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "std")]
+use chunklist::format::StringCodec;
+use chunklist::{ChunkList, DiskChunkList};
+
+#[test]
+fn disk_list_spills_within_budget() {
+    // Room for about four chunks of 100 u64s
+    let budget = 4 * 100 * 8;
+    let mut list = DiskChunkList::new(100, budget).unwrap();
+    let mut mirror = ChunkList::new(100);
+    for i in 0..5_000u64 {
+        list.add(i).unwrap();
+        mirror.add(i);
+        assert!(list.resident_bytes() <= budget);
+    }
+    assert_eq!(list.len(), 5_000);
+    assert_eq!(list.chunk_amount(), mirror.chunk_amount());
+    assert!(list.resident_chunks() <= 4);
+
+    // Random access reads spilled chunks back, writes dirty them again
+    for i in (0..5_000).step_by(97) {
+        assert_eq!(list.get(i).unwrap(), i as u64);
+        assert_eq!(list.set(i, i as u64 * 2).unwrap(), i as u64);
+        mirror.set(i, i as u64 * 2);
+    }
+    assert!(list.resident_bytes() <= budget);
+    assert_eq!(list.get_list().unwrap(), mirror.get_list());
+    assert_eq!(list.to_chunk_list().unwrap().get_list(), mirror.get_list());
+
+    // Parallel scans stream every chunk through the cache
+    assert!(list.contains(&4_999).unwrap());
+    assert!(list.contains(&(97 * 2)).unwrap());
+    assert!(!list.contains(&10_000).unwrap());
+    let sums = list.par_map_chunks(|chunk| chunk.iter().sum::<u64>()).unwrap();
+    assert_eq!(sums.iter().sum::<u64>(), mirror.get_list().iter().sum::<u64>());
+    assert!(list.resident_bytes() <= budget);
+}
+
+#[test]
+fn disk_list_with_custom_codec_and_dir() {
+    let dir = std::env::temp_dir().join(format!("chunklist-disk-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    {
+        // A zero budget keeps nothing resident between calls
+        let mut words = DiskChunkList::with_codec(3, 0, &dir, StringCodec).unwrap();
+        for i in 0..20 {
+            words.add(format!("word {}", i)).unwrap();
+        }
+        assert_eq!(words.resident_chunks(), 0);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        // Growing a chunk past its old spilled extent moves it
+        words.set(4, "a much longer word than before".to_string()).unwrap();
+        assert_eq!(words.get(4).unwrap(), "a much longer word than before");
+        assert_eq!(words.get(5).unwrap(), "word 5");
+        assert_eq!(words.get(19).unwrap(), "word 19");
+    }
+    // The spill file is removed on drop
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    std::fs::remove_dir(&dir).unwrap();
+}
+
+#[test]
+fn disk_list_reuses_freed_spill_space() {
+    let dir = std::env::temp_dir().join(format!("chunklist-disk-grow-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    {
+        let mut words = DiskChunkList::with_codec(4, 0, &dir, StringCodec).unwrap();
+        for i in 0..40 {
+            words.add(format!("word {}", i)).unwrap();
+        }
+        let spill = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let initial = std::fs::metadata(&spill).unwrap().len();
+
+        // Growing a chunk over and over moves it each time; the extents it leaves
+        // behind must be reused instead of piling up at the end of the file
+        for i in 1..=100 {
+            words.set(10, "x".repeat(i * 10)).unwrap();
+            let size = std::fs::metadata(&spill).unwrap().len();
+            assert!(size <= initial + 3 * 1_100, "spill file grew to {} bytes", size);
+        }
+        assert_eq!(words.get(10).unwrap().len(), 1_000);
+        assert_eq!(words.get(39).unwrap(), "word 39");
+    }
+    std::fs::remove_dir(&dir).unwrap();
+}
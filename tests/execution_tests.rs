@@ -0,0 +1,106 @@
+#![cfg(feature = "rayon")]
+use chunklist::{ChunkList, ExecutionConfig};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+fn list(len: u32) -> ChunkList<u32> {
+    let mut list = ChunkList::new(16);
+    for i in 0..len {
+        list.add(i);
+    }
+    list
+}
+
+/// Names of the threads `par_for_each_chunk` ran on.
+fn kernel_threads(list: &mut ChunkList<u32>) -> HashSet<String> {
+    let names = Mutex::new(HashSet::new());
+    list.par_for_each_chunk(|_, _| {
+        let name = thread::current().name().unwrap_or("").to_string();
+        names.lock().unwrap().insert(name);
+    });
+    names.into_inner().unwrap()
+}
+
+#[test]
+fn execution_custom_pool_and_cutoff() {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(2)
+        .thread_name(|i| format!("custom-{}", i))
+        .build()
+        .unwrap();
+    let mut big = list(10_000);
+    big.set_execution(ExecutionConfig::new().with_pool(Arc::new(pool)).with_sequential_cutoff(100));
+    let names = kernel_threads(&mut big);
+    assert!(names.iter().all(|name| name.starts_with("custom-")), "{:?}", names);
+    assert!(big.contains(&9_999));
+    big.remove(&5);
+    assert_eq!(big.index_of(&6), Some(5));
+
+    // Below the cutoff everything stays on the calling thread
+    let current = thread::current().name().unwrap_or("").to_string();
+    let mut small = list(50);
+    small.set_execution(ExecutionConfig::new().with_sequential_cutoff(100));
+    assert_eq!(kernel_threads(&mut small), HashSet::from([current]));
+    assert_eq!(small.get_execution().get_sequential_cutoff(), 100);
+}
+
+#[test]
+fn execution_sequential_mode() {
+    let current = thread::current().name().unwrap_or("").to_string();
+    let mut list = list(20_000);
+    assert!(!list.get_execution().is_sequential());
+
+    // Per call: only the operations inside the closure run sequentially
+    let names = list.with_execution(ExecutionConfig::sequential(), |list| {
+        list.remove_all(&7);
+        list.sort();
+        kernel_threads(list)
+    });
+    assert_eq!(names, HashSet::from([current]));
+    assert!(!list.contains(&7));
+    assert_eq!(list.len(), 19_999);
+    assert!(!list.get_execution().is_sequential());
+}
+
+#[test]
+fn execution_deterministic_mode() {
+    let config = ExecutionConfig::new().with_deterministic(7);
+    assert!(config.is_deterministic());
+    assert_eq!(config.get_seed(), Some(7));
+    assert!(!ExecutionConfig::new().is_deterministic());
+
+    // Every chunk holds a 3, so without deterministic mode any of them could lose it
+    for _ in 0..10 {
+        let mut list = list(20_000);
+        list.par_for_each_chunk(|_, chunk| chunk[5] = 3);
+        list.set_execution(config.clone());
+        list.remove(&3);
+        assert_eq!(list.index_of(&3), Some(4));
+        assert_eq!(list.count(&3), 1_250);
+    }
+
+    // Floating point sums come out bit-for-bit the same, in chunk order
+    let mut floats = ChunkList::new(100);
+    for i in 0..100_000u32 {
+        floats.add((i as f64).sin().to_bits());
+    }
+    floats.set_execution(config);
+    let sum = |floats: &ChunkList<u64>| {
+        floats.fold(|| 0.0, |acc, x| acc + f64::from_bits(*x), |a, b| a + b)
+    };
+    let expected = floats
+        .snapshot()
+        .chunks()
+        .map(|chunk| chunk.iter().fold(0.0, |acc, x| acc + f64::from_bits(*x)))
+        .fold(0.0, |a, b| a + b);
+    for _ in 0..10 {
+        assert_eq!(sum(&floats).to_bits(), expected.to_bits());
+    }
+
+    // Extending fills whole chunks
+    floats.par_extend((0..250u64).into_par_iter());
+    let sizes: Vec<usize> = floats.snapshot().chunks().map(|chunk| chunk.len()).skip(1_000).collect();
+    assert_eq!(sizes, [100, 100, 50]);
+}
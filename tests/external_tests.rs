@@ -0,0 +1,50 @@
+#![cfg(feature = "std")]
+use chunklist::format::StringCodec;
+use chunklist::{ChunkList, ExternalSort};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[test]
+fn external_sort_matches_in_memory_sort() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let items: Vec<i64> = (0..20_000).map(|_| rng.gen_range(-1_000..1_000)).collect();
+    let mut expected = items.clone();
+    expected.sort();
+
+    // Room for about 4 chunks: 50 runs need a second merge pass
+    let sorter = ExternalSort::new(400, 4 * 400 * 8);
+    let sorted = sorter.sort(items.iter().copied()).unwrap();
+    assert_eq!(sorted.get_list(), expected);
+    assert_eq!(sorted.get_chunk_size(), 400);
+    assert_eq!(sorted.chunk_amount(), 50);
+
+    let disk = sorter.sort_to_disk_list(items.iter().copied(), 2 * 400 * 8).unwrap();
+    assert_eq!(disk.get_list().unwrap(), expected);
+    assert!(disk.resident_chunks() <= 2);
+
+    let path = std::env::temp_dir().join(format!("chunklist-{}-sorted.chkl", std::process::id()));
+    sorter.sort_to_file(items, &path).unwrap();
+    let loaded = ChunkList::<i64>::load(&path).unwrap();
+    assert_eq!(loaded.get_list(), expected);
+    assert_eq!(loaded.chunk_amount(), 50);
+    std::fs::remove_file(&path).unwrap();
+
+    // Empty input
+    assert!(sorter.sort(Vec::<i64>::new()).unwrap().is_empty());
+}
+
+#[test]
+fn external_sort_strings_in_temp_dir() {
+    let dir = std::env::temp_dir().join(format!("chunklist-external-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let words: Vec<String> = (0..1_000).map(|i| format!("word {}", (i * 7919) % 1_000)).collect();
+    let mut expected = words.clone();
+    expected.sort();
+
+    let sorter = ExternalSort::new(16, 0).with_temp_dir(&dir);
+    let sorted = sorter.sort_with(words, &StringCodec).unwrap();
+    assert_eq!(sorted.get_list(), expected);
+    // Spilled runs are cleaned up
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    std::fs::remove_dir(&dir).unwrap();
+}
@@ -0,0 +1,191 @@
+#![cfg(feature = "std")]
+use chunklist::format::{ChunkCodec, CodecError, FloatCodec, IntCodec};
+use chunklist::{ChunkFileReader, ChunkList, FormatError, LoadMode, WriteOptions};
+use std::io::Cursor;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("chunklist-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn format_save_load_round_trip() {
+    let mut numbers = ChunkList::new(100);
+    for i in 0..1_050i64 {
+        numbers.add(i * 3 - 500);
+    }
+    numbers.remove_at(10);
+    numbers.insert(0, i64::MAX);
+
+    let path = temp_path("numbers.chkl");
+    numbers.save(&path).unwrap();
+    let loaded = ChunkList::<i64>::load(&path).unwrap();
+    assert_eq!(loaded.get_list(), numbers.get_list());
+    assert_eq!(loaded.get_chunk_size(), 100);
+    assert_eq!(loaded.chunk_amount(), numbers.chunk_amount());
+
+    // Individual chunks can be read without decoding the rest
+    let mut reader = ChunkFileReader::open(&path).unwrap();
+    assert_eq!(reader.chunk_amount(), numbers.chunk_amount());
+    assert_eq!(reader.len(), numbers.len());
+    assert_eq!(reader.header().element_width, 8);
+    let last = reader.chunk_amount() - 1;
+    let chunk: Vec<i64> = reader.read_chunk(last, &IntCodec).unwrap();
+    assert_eq!(chunk, numbers.get_list()[numbers.len() - chunk.len()..]);
+    assert!(matches!(
+        reader.read_chunk::<i64, _>(last + 1, &IntCodec),
+        Err(FormatError::ChunkOutOfRange { .. })
+    ));
+    std::fs::remove_file(&path).unwrap();
+
+    // Variable-width strings through an in-memory buffer
+    let mut words = ChunkList::new(2);
+    for word in ["chunk", "", "list", "ünïcode"] {
+        words.add(word.to_string());
+    }
+    let mut buffer = Vec::new();
+    words.write_to(&mut buffer).unwrap();
+    let restored = ChunkList::<String>::read_from(buffer.as_slice()).unwrap();
+    assert_eq!(restored.get_list(), words.get_list());
+    let mut reader = ChunkFileReader::new(Cursor::new(buffer)).unwrap();
+    assert_eq!(reader.header().element_width, 0);
+    assert_eq!(reader.read_chunk(1, &chunklist::format::StringCodec).unwrap(), vec!["list", "ünïcode"]);
+}
+
+#[test]
+fn format_custom_codec_and_errors() {
+    // A user codec storing (id, score) pairs, with the score as a float
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    struct Entry {
+        id: u32,
+        score_bits: u64,
+    }
+    struct EntryCodec;
+    impl ChunkCodec<Entry> for EntryCodec {
+        fn element_width(&self) -> Option<usize> {
+            Some(12)
+        }
+        fn encode(&self, item: &Entry, out: &mut Vec<u8>) {
+            IntCodec.encode(&item.id, out);
+            FloatCodec.encode(&f64::from_bits(item.score_bits), out);
+        }
+        fn decode(&self, input: &mut &[u8]) -> Result<Entry, CodecError> {
+            let id = IntCodec.decode(input)?;
+            let score: f64 = FloatCodec.decode(input)?;
+            Ok(Entry { id, score_bits: score.to_bits() })
+        }
+    }
+
+    let mut list = ChunkList::new(4);
+    for id in 0..10 {
+        list.add(Entry { id, score_bits: (id as f64 / 3.0).to_bits() });
+    }
+    let mut buffer = Vec::new();
+    list.write_with(&mut buffer, &EntryCodec).unwrap();
+    assert_eq!(ChunkList::read_with(buffer.as_slice(), &EntryCodec).unwrap().get_list(), list.get_list());
+
+    // Corrupt the magic, truncate the file and break a payload
+    let mut bad_magic = buffer.clone();
+    bad_magic[0] = b'X';
+    assert!(matches!(ChunkList::read_with(bad_magic.as_slice(), &EntryCodec), Err(FormatError::BadMagic)));
+
+    let truncated = &buffer[..buffer.len() - 5];
+    assert!(matches!(ChunkList::read_with(truncated, &EntryCodec), Err(FormatError::Io(_))));
+
+    let mut ints = ChunkList::new(4);
+    for x in 0..8u32 {
+        ints.add(x);
+    }
+    let mut buffer = Vec::new();
+    ints.write_to(&mut buffer).unwrap();
+    // Reading u32 payloads as u64 leaves half an element over in each chunk
+    assert!(matches!(
+        ChunkList::<u64>::read_from(buffer.as_slice()),
+        Err(FormatError::InvalidChunk { index: 0, .. })
+    ));
+}
+
+#[test]
+fn format_checksums_detect_corrupt_chunks() {
+    let mut list = ChunkList::new(4);
+    for i in 0..12u32 {
+        list.add(i);
+    }
+    let mut buffer = Vec::new();
+    list.write_to(&mut buffer).unwrap();
+    let header = ChunkFileReader::new(Cursor::new(&buffer)).unwrap().header().clone();
+    assert_eq!(header.checksums.as_deref(), Some(&list.checksums()[..]));
+
+    // Flip a byte in the middle chunk: still decodes, but fails its checksum
+    buffer[header.directory[1].offset as usize] ^= 0xFF;
+    let mut reader = ChunkFileReader::new(Cursor::new(&buffer)).unwrap();
+    let corrupt = reader.verify().unwrap();
+    assert_eq!(corrupt.len(), 1);
+    assert_eq!(corrupt[0].index, 1);
+    assert!(matches!(corrupt[0].error, FormatError::ChecksumMismatch { index: 1, .. }));
+    assert_eq!(reader.read_chunk::<u32, _>(0, &IntCodec).unwrap(), vec![0, 1, 2, 3]);
+    assert_eq!(reader.read_chunk::<u32, _>(1, &IntCodec).unwrap_err().chunk_index(), Some(1));
+
+    let error = ChunkList::<u32>::read_from(buffer.as_slice()).unwrap_err();
+    assert!(matches!(error, FormatError::ChecksumMismatch { index: 1, .. }));
+
+    let skipped = ChunkList::<u32>::read_checked_with(buffer.as_slice(), &IntCodec, LoadMode::Skip).unwrap();
+    assert_eq!(skipped.list.get_list(), vec![0, 1, 2, 3, 8, 9, 10, 11]);
+    assert_eq!(skipped.corrupt.len(), 1);
+    assert!(skipped.corrupt[0].payload.is_none());
+
+    let quarantined =
+        ChunkList::<u32>::read_checked_with(buffer.as_slice(), &IntCodec, LoadMode::Quarantine).unwrap();
+    assert_eq!(quarantined.list.chunk_amount(), 2);
+    let payload = quarantined.corrupt[0].payload.as_ref().unwrap();
+    assert_eq!(payload.len(), 16);
+    assert_eq!(payload[0], 4 ^ 0xFF);
+
+    // A checksum is computed from the payload when asked for
+    assert_eq!(reader.chunk_checksum(2).unwrap(), list.checksums()[2]);
+
+    // Without checksums there is nothing for `verify` to check against
+    let mut plain = Vec::new();
+    list.write_with_options(&mut plain, &IntCodec, WriteOptions::new().with_checksums(false)).unwrap();
+    let mut reader = ChunkFileReader::new(Cursor::new(&plain)).unwrap();
+    assert!(!reader.has_checksums());
+    assert!(matches!(reader.verify(), Err(FormatError::NoChecksums)));
+    assert!(reader.verify_with::<u32, _>(&IntCodec).unwrap().is_empty());
+    assert_eq!(reader.chunk_checksum(2).unwrap(), list.checksums()[2]);
+    assert_eq!(ChunkList::<u32>::read_from(plain.as_slice()).unwrap().get_list(), list.get_list());
+}
+
+#[test]
+fn format_float_round_trip() {
+    use chunklist::{TotalF32, TotalF64};
+
+    let mut list = ChunkList::new(4);
+    for x in [2.5, -0.0, f64::NAN, 0.0, f64::NEG_INFINITY, -1e300, f64::INFINITY, 1.0 / 3.0, -f64::NAN] {
+        list.add(TotalF64(x));
+    }
+    list.sort();
+    let bits = |list: &ChunkList<TotalF64>| list.get_list().iter().map(|x| x.get().to_bits()).collect::<Vec<_>>();
+    let expected = [-f64::NAN, f64::NEG_INFINITY, -1e300, -0.0, 0.0, 1.0 / 3.0, 2.5, f64::INFINITY, f64::NAN];
+    assert_eq!(bits(&list), expected.map(f64::to_bits));
+    assert!(list.contains(&TotalF64(f64::NAN)));
+    assert_ne!(TotalF64(0.0), TotalF64(-0.0));
+
+    let path = temp_path("floats.chkl");
+    list.save(&path).unwrap();
+    assert_eq!(ChunkFileReader::open(&path).unwrap().header().element_width, 8);
+    let loaded = ChunkList::<TotalF64>::load(&path).unwrap();
+    assert_eq!(bits(&loaded), bits(&list));
+    assert_eq!(loaded.chunk_amount(), list.chunk_amount());
+
+    // The same file read as plain floats
+    let mut reader = ChunkFileReader::open(&path).unwrap();
+    let first: Vec<f64> = reader.read_chunk(0, &FloatCodec).unwrap();
+    assert_eq!(first.iter().map(|x| x.to_bits()).collect::<Vec<_>>(), bits(&list)[..4]);
+
+    let mut small = ChunkList::new(2);
+    small.add(TotalF32(1.5));
+    small.add(TotalF32(-2.0));
+    small.save(&path).unwrap();
+    assert_eq!(ChunkList::<TotalF32>::load(&path).unwrap().get_list(), [TotalF32(1.5), TotalF32(-2.0)]);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "std")]
+use chunklist::{ChunkList, HistoryChunkList};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Elements and exact chunk layout of a list.
+fn layout(list: &ChunkList<i32>) -> Vec<Vec<i32>> {
+    list.snapshot().chunks().map(|chunk| chunk.to_vec()).collect()
+}
+
+#[test]
+fn history_undo_restores_exact_layout() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut list = HistoryChunkList::new(8);
+    // A plain list receiving the same operations must end up with the same layout
+    let mut mirror = ChunkList::new(8);
+    let mut states = vec![layout(list.list())];
+
+    for _ in 0..300 {
+        let len = list.list().len();
+        match rng.gen_range(0..6) {
+            0 => {
+                let x = rng.gen_range(0..20);
+                list.add(x);
+                mirror.add(x);
+            }
+            1 if len > 0 => {
+                let (i, x) = (rng.gen_range(0..len), rng.gen_range(0..20));
+                list.set(i, x);
+                mirror.set(i, x);
+            }
+            2 => {
+                let (i, x) = (rng.gen_range(0..=len), rng.gen_range(0..20));
+                list.insert(i, x);
+                mirror.insert(i, x);
+            }
+            3 if len > 0 => {
+                let i = rng.gen_range(0..len);
+                assert_eq!(list.remove_at(i), mirror.remove_at(i));
+            }
+            4 => {
+                let x = rng.gen_range(0..20);
+                list.remove_all(&x);
+                mirror.remove_all(&x);
+            }
+            5 => {
+                list.sort();
+                mirror.sort();
+            }
+            _ => continue,
+        }
+        let state = layout(list.list());
+        assert_eq!(state, layout(&mirror));
+        if states.last() != Some(&state) {
+            states.push(state);
+        }
+    }
+
+    // Walk all the way back, then all the way forward again
+    let final_state = layout(list.list());
+    while list.undo() {}
+    assert_eq!(layout(list.list()), states[0]);
+    assert!(!list.can_undo());
+    while list.redo() {}
+    assert_eq!(layout(list.list()), final_state);
+}
+
+#[test]
+fn history_checkpoints_and_memory_limit() {
+    let mut base = ChunkList::new(4);
+    for x in [5, 3, 9, 1, 7] {
+        base.add(x);
+    }
+    let mut list = HistoryChunkList::from(base);
+
+    list.checkpoint("edit");
+    list.add(4);
+    list.set(0, 50);
+    list.remove_at(1);
+    list.end_checkpoint();
+    list.sort();
+    assert_eq!(list.list().get_list(), vec![1, 4, 7, 9, 50]);
+
+    // The sort is undone on its own, then the whole checkpoint at once
+    assert_eq!(list.undo_name(), None);
+    assert!(list.undo());
+    assert_eq!(list.undo_name(), Some("edit"));
+    assert!(list.undo());
+    assert_eq!(list.list().get_list(), vec![5, 3, 9, 1, 7]);
+    assert_eq!(list.redo_name(), Some("edit"));
+    assert!(list.redo());
+    assert_eq!(list.list().get_list(), vec![50, 9, 1, 7, 4]);
+
+    // A new operation discards what could be redone
+    list.remove_all(&9);
+    assert!(!list.can_redo());
+
+    // With a tight memory limit, only the most recent operations are kept
+    let mut bounded = HistoryChunkList::new(100).with_memory_limit(1_000);
+    for x in 0..1_000 {
+        bounded.add(x);
+    }
+    assert!(bounded.memory_usage() <= 1_000);
+    let mut undone = 0;
+    while bounded.undo() {
+        undone += 1;
+    }
+    assert!(undone > 0 && undone < 1_000);
+    assert_eq!(bounded.list().len(), 1_000 - undone);
+}
+
+#[test]
+fn history_changes_reach_observers() {
+    use chunklist::ChunkEvent;
+    use std::sync::{Arc, Mutex};
+
+    // A plain Vec kept up to date from the events alone must always match the list
+    let replayed = Arc::new(Mutex::new(Vec::new()));
+    let mut base = ChunkList::new(4);
+    #[cfg(feature = "rayon")]
+    base.set_execution(chunklist::ExecutionConfig::new().with_sequential_cutoff(1));
+    let sink = replayed.clone();
+    base.subscribe(move |event: &ChunkEvent<i32>| {
+        let mut items = sink.lock().unwrap();
+        match event {
+            ChunkEvent::Inserted { index, value } => items.insert(*index, *value),
+            ChunkEvent::Removed { index, value } => assert_eq!(items.remove(*index), *value),
+            ChunkEvent::Updated { index, old, new } => {
+                assert_eq!(items[*index], *old);
+                items[*index] = *new;
+            }
+            ChunkEvent::Sorted => items.sort(),
+            ChunkEvent::Cleared => items.clear(),
+            ChunkEvent::ChunkSplit { .. } | ChunkEvent::Rebalanced { .. } => {}
+        }
+    });
+    let mut list = HistoryChunkList::from(base);
+
+    let mut rng = StdRng::seed_from_u64(5);
+    for _ in 0..200 {
+        let len = list.list().len();
+        match rng.gen_range(0..8) {
+            0 | 1 => list.add(rng.gen_range(0..10)),
+            2 if len > 0 => list.set(rng.gen_range(0..len), rng.gen_range(0..10)),
+            3 => list.insert(rng.gen_range(0..=len), rng.gen_range(0..10)),
+            4 if len > 0 => {
+                list.remove_at(rng.gen_range(0..len));
+            }
+            5 => list.remove_all(&rng.gen_range(0..10)),
+            6 => list.sort(),
+            _ => {
+                list.undo();
+            }
+        }
+        assert_eq!(*replayed.lock().unwrap(), list.list().get_list());
+    }
+    while list.undo() {
+        assert_eq!(*replayed.lock().unwrap(), list.list().get_list());
+    }
+    while list.redo() {
+        assert_eq!(*replayed.lock().unwrap(), list.list().get_list());
+    }
+}
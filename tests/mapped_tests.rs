@@ -0,0 +1,93 @@
+// Run with "cargo test --features mmap"
+#![cfg(feature = "mmap")]
+use chunklist::{ChunkList, FormatError, MappedChunkList};
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("chunklist-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn mapped_matches_saved_list() {
+    let mut list = ChunkList::new(64);
+    for i in 0..1_000u64 {
+        list.add(i * i);
+    }
+    list.remove_at(3);
+    list.insert(500, 7);
+
+    let path = temp_path("mapped.chkl");
+    list.save(&path).unwrap();
+    let mapped = MappedChunkList::<u64>::open(&path).unwrap();
+
+    assert_eq!(mapped.len(), list.len());
+    assert_eq!(mapped.get_chunk_size(), 64);
+    assert_eq!(mapped.chunk_amount(), list.chunk_amount());
+    assert_eq!(mapped.get_list(), list.get_list());
+    for i in [0, 1, 63, 64, 499, 500, 501, list.len() - 1] {
+        assert_eq!(mapped.get(i), list.get(i));
+    }
+    assert!(mapped.contains(&7));
+    assert!(mapped.contains(&(999 * 999)));
+    assert!(!mapped.contains(&3));
+    assert_eq!(mapped.iter().count(), list.len());
+    assert_eq!(mapped.to_chunk_list().get_list(), list.get_list());
+
+    // Custom parallel scans over the mapped chunks
+    use rayon::prelude::*;
+    let sum: u64 = mapped.par_chunks().map(|chunk| chunk.iter().sum::<u64>()).sum();
+    assert_eq!(sum, list.get_list().iter().sum::<u64>());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn mapped_rejects_mismatched_files() {
+    let mut list = ChunkList::new(8);
+    for i in 0..20u32 {
+        list.add(i);
+    }
+    let path = temp_path("mapped-u32.chkl");
+    list.save(&path).unwrap();
+
+    // Wrong element width
+    assert!(matches!(MappedChunkList::<u64>::open(&path), Err(FormatError::InvalidHeader(_))));
+    assert_eq!(MappedChunkList::<u32>::open(&path).unwrap().get_list(), list.get_list());
+
+    // Variable-width payloads can't be mapped
+    let mut words = ChunkList::new(8);
+    words.add("not plain old data".to_string());
+    words.save(&path).unwrap();
+    assert!(MappedChunkList::<u8>::open(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn mapped_rejects_overflowing_counts() {
+    let mut list = ChunkList::new(4);
+    for i in 0..8u64 {
+        list.add(i);
+    }
+    let path = temp_path("mapped-overflow.chkl");
+    list.save(&path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    let read_u64 = |bytes: &[u8], at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+    let write_u64 = |bytes: &mut Vec<u8>, at: usize, value: u64| bytes[at..at + 8].copy_from_slice(&value.to_le_bytes());
+    // Header: element count at 24, directory entries (offset, length, count) from 40
+    let (element_count, first_count, second_count) = (24, 40 + 16, 40 + 24 + 16);
+
+    // A count whose byte length wraps around to the stored length
+    let mut corrupt = bytes.clone();
+    write_u64(&mut corrupt, first_count, read_u64(&bytes, first_count) + (1 << 61));
+    write_u64(&mut corrupt, element_count, read_u64(&bytes, element_count) + (1 << 61));
+    std::fs::write(&path, &corrupt).unwrap();
+    assert!(matches!(MappedChunkList::<u64>::open(&path), Err(FormatError::InvalidHeader(_))));
+
+    // Counts whose sum wraps around to the stored element count
+    let mut corrupt = bytes.clone();
+    write_u64(&mut corrupt, first_count, read_u64(&bytes, first_count) + (1 << 63));
+    write_u64(&mut corrupt, second_count, read_u64(&bytes, second_count) + (1 << 63));
+    std::fs::write(&path, &corrupt).unwrap();
+    assert!(matches!(MappedChunkList::<u64>::open(&path), Err(FormatError::InvalidHeader(_))));
+    assert!(ChunkList::<u64>::load(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,122 @@
+#![cfg(feature = "std")]
+use chunklist::format::IntCodec;
+use chunklist::{ChunkEvent, ChunkList, ChunkPatch, MerkleTree, PatchError};
+use std::sync::{Arc, Mutex};
+
+fn layout(list: &ChunkList<u32>) -> Vec<Vec<u32>> {
+    list.snapshot().chunks().map(|chunk| chunk.to_vec()).collect()
+}
+
+#[test]
+fn merkle_diff_finds_changed_chunks() {
+    let mut leader = ChunkList::new(10);
+    for i in 0..1_000u32 {
+        leader.add(i);
+    }
+    let mut replica = ChunkList::new(10);
+    for i in 0..1_000u32 {
+        replica.add(i);
+    }
+    assert_eq!(leader.merkle_tree().root(), replica.merkle_tree().root());
+    assert!(leader.diff(&replica).is_empty());
+
+    leader.set(5, 0);
+    leader.set(555, 0);
+    leader.set(999, 0);
+    assert_eq!(leader.diff(&replica), vec![0, 55, 99]);
+    assert_ne!(leader.merkle_tree().root(), replica.merkle_tree().root());
+
+    // Chunks that exist on one side only count as different
+    for i in 0..25 {
+        leader.add(i);
+    }
+    assert_eq!(leader.diff(&replica), vec![0, 55, 99, 100, 101, 102]);
+    assert_eq!(replica.diff(&leader), vec![0, 55, 99, 100, 101, 102]);
+}
+
+#[test]
+fn merkle_patch_syncs_replica() {
+    let mut leader = ChunkList::new(4);
+    for i in 0..40u32 {
+        leader.add(i);
+    }
+    let mut replica = ChunkList::new(4);
+    for i in 0..40u32 {
+        replica.add(i);
+    }
+    leader.set(9, 90);
+    leader.insert(20, 200);
+    for _ in 0..10 {
+        leader.remove_at(leader.len() - 1);
+    }
+
+    // The replica sends its hashes, the leader answers with only the differing chunks
+    let mut hashes = Vec::new();
+    replica.merkle_tree().write_to(&mut hashes).unwrap();
+    let patch = leader.patch_for(&MerkleTree::read_from(hashes.as_slice()).unwrap());
+    let patched: Vec<usize> = patch.chunks.iter().map(|(index, _)| *index).collect();
+    assert_eq!(patched, leader.diff(&replica));
+    assert!(!patched.contains(&0) && !patched.contains(&1) && !patched.contains(&3));
+    let mut bytes = Vec::new();
+    patch.write_with(&mut bytes, &IntCodec).unwrap();
+    let received = ChunkPatch::read_with(bytes.as_slice(), &IntCodec).unwrap();
+    assert_eq!(received, patch);
+
+    let events = Arc::new(Mutex::new(0));
+    let counter = events.clone();
+    let id = replica.subscribe(move |event: &ChunkEvent<u32>| {
+        assert!(matches!(event, ChunkEvent::Removed { .. } | ChunkEvent::Inserted { .. }));
+        *counter.lock().unwrap() += 1;
+    });
+    replica.apply_patch(received).unwrap();
+    replica.unsubscribe(id);
+    assert_eq!(replica.get_list(), leader.get_list());
+    assert_eq!(layout(&replica), layout(&leader));
+    assert_eq!(replica.merkle_tree().root(), leader.merkle_tree().root());
+    assert!(*events.lock().unwrap() > 0);
+
+    // A patch made against an older state of the replica is rejected and changes nothing
+    leader.set(0, 1_000);
+    let stale = leader.patch_for(&replica.merkle_tree());
+    replica.set(4, 4_000);
+    let before = layout(&replica);
+    assert!(matches!(replica.apply_patch(stale), Err(PatchError::RootMismatch { .. })));
+    assert_eq!(layout(&replica), before);
+}
+
+#[test]
+fn merkle_diff_skips_shared_chunks() {
+    use chunklist::format::{ChunkCodec, CodecError};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// IntCodec that counts the elements it encodes.
+    struct Counting(AtomicUsize);
+
+    impl ChunkCodec<u32> for Counting {
+        fn encode(&self, item: &u32, out: &mut Vec<u8>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            IntCodec.encode(item, out);
+        }
+
+        fn decode(&self, input: &mut &[u8]) -> Result<u32, CodecError> {
+            IntCodec.decode(input)
+        }
+    }
+
+    let mut leader = ChunkList::new(10);
+    for i in 0..10_000u32 {
+        leader.add(i);
+    }
+    // The replica shares every chunk until one side writes to it
+    let mut replica = leader.snapshot().to_chunk_list();
+    let codec = Counting(AtomicUsize::new(0));
+    assert!(leader.diff_with(&replica, &codec).is_empty());
+    assert_eq!(codec.0.load(Ordering::Relaxed), 0);
+
+    leader.set(15, 0);
+    replica.set(9_995, 1);
+    assert_eq!(leader.diff_with(&replica, &codec), vec![1, 999]);
+    // Only the two copied chunks, on both sides
+    assert_eq!(codec.0.load(Ordering::Relaxed), 4 * 10);
+    assert_eq!(leader.diff(&replica), vec![1, 999]);
+}
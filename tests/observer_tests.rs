@@ -0,0 +1,90 @@
+use chunklist::{ChunkEvent, ChunkList};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn observer_events_replay_onto_mirror() {
+    let mut rng = StdRng::seed_from_u64(13);
+    let mut list = ChunkList::new(16);
+    let mirror = Arc::new(Mutex::new(Vec::new()));
+
+    // Replaying the events on a plain Vec must reproduce the list
+    let replay = Arc::clone(&mirror);
+    list.subscribe(move |event: &ChunkEvent<i32>| {
+        let mut mirror = replay.lock().unwrap();
+        match event {
+            ChunkEvent::Inserted { index, value } => mirror.insert(*index, *value),
+            ChunkEvent::Removed { index, value } => assert_eq!(mirror.remove(*index), *value),
+            ChunkEvent::Updated { index, old, new } => {
+                assert_eq!(mirror[*index], *old);
+                mirror[*index] = *new;
+            }
+            ChunkEvent::Sorted => mirror.sort(),
+            ChunkEvent::Cleared => mirror.clear(),
+            ChunkEvent::ChunkSplit { .. } | ChunkEvent::Rebalanced { .. } => {}
+        }
+    });
+
+    for _ in 0..2_000 {
+        let len = list.len();
+        match rng.gen_range(0..9) {
+            0 | 1 => list.add(rng.gen_range(0..50)),
+            2 if len > 0 => list.set(rng.gen_range(0..len), rng.gen_range(0..50)),
+            3 => list.insert(rng.gen_range(0..=len), rng.gen_range(0..50)),
+            4 if len > 0 => {
+                list.remove_at(rng.gen_range(0..len));
+            }
+            5 => list.remove(&rng.gen_range(0..50)),
+            6 => list.remove_all(&rng.gen_range(0..50)),
+            7 => list.sort(),
+            8 => list.set_chunk_size(rng.gen_range(4..32)),
+            _ => continue,
+        }
+        assert_eq!(*mirror.lock().unwrap(), list.get_list());
+    }
+    list.clear();
+    assert!(mirror.lock().unwrap().is_empty());
+}
+
+#[test]
+fn observer_batches_and_unsubscribe() {
+    let mut list = ChunkList::new(4);
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&batches);
+    let id = list.subscribe_batched(move |events: &[ChunkEvent<i32>]| {
+        sink.lock().unwrap().push(events.to_vec());
+    });
+
+    for x in [1, 2, 3, 2, 5, 2] {
+        list.add(x);
+    }
+    list.insert(0, 0);
+    list.remove_all(&2);
+
+    let batches_seen = batches.lock().unwrap().clone();
+    assert_eq!(batches_seen.len(), 8);
+    // Inserting into the full first chunk splits it
+    assert_eq!(
+        batches_seen[6],
+        vec![
+            ChunkEvent::Inserted { index: 0, value: 0 },
+            ChunkEvent::ChunkSplit { chunk_index: 0, at: 2 },
+        ]
+    );
+    // The parallel `remove_all` is reported as one batch, indices adjusted in order
+    assert_eq!(
+        batches_seen[7],
+        vec![
+            ChunkEvent::Removed { index: 2, value: 2 },
+            ChunkEvent::Removed { index: 3, value: 2 },
+            ChunkEvent::Removed { index: 4, value: 2 },
+        ]
+    );
+    assert_eq!(list.get_list(), vec![0, 1, 3, 5]);
+
+    assert!(list.unsubscribe(id));
+    assert!(!list.unsubscribe(id));
+    list.add(9);
+    assert_eq!(batches.lock().unwrap().len(), 8);
+}
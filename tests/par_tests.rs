@@ -0,0 +1,93 @@
+#![cfg(feature = "rayon")]
+use chunklist::{ChunkEvent, ChunkList};
+use rayon::prelude::*;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn par_iter_preserves_order() {
+    let mut list = ChunkList::new(7);
+    for i in 0..1_000u32 {
+        list.add(i);
+    }
+    list.remove_at(10);
+    list.remove_at(500);
+    let expected = list.get_list();
+
+    // Chunks are partly filled now; indexed adaptors still see global order
+    assert_eq!(list.par_iter().len(), expected.len());
+    let collected: Vec<u32> = list.par_iter().copied().collect();
+    assert_eq!(collected, expected);
+    assert!(list.par_iter().enumerate().all(|(i, &x)| x == expected[i]));
+    let pairs: Vec<(u32, u32)> = list.par_iter().zip(expected.par_iter()).map(|(a, b)| (*a, *b)).collect();
+    assert!(pairs.iter().all(|(a, b)| a == b));
+    assert_eq!(list.par_iter().rev().copied().collect::<Vec<_>>(), expected.iter().rev().copied().collect::<Vec<_>>());
+
+    // Mutable iteration leaves snapshots untouched
+    let snapshot = list.snapshot();
+    list.par_iter_mut().for_each(|x| *x *= 2);
+    assert_eq!(list.get_list(), expected.iter().map(|x| x * 2).collect::<Vec<_>>());
+    assert_eq!(snapshot.get_list(), expected);
+
+    let owned: Vec<u32> = list.into_par_iter().with_min_len(3).collect();
+    assert_eq!(owned, expected.iter().map(|x| x * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn par_collect_and_extend() {
+    let list: ChunkList<u64> = (0..5_000u64).into_par_iter().map(|i| i * i).collect();
+    assert_eq!(list.get_chunk_size(), 1000);
+    assert_eq!(list.len(), 5_000);
+    assert!(list.chunk_amount() >= 5);
+    assert_eq!(list.get_list(), (0..5_000u64).map(|i| i * i).collect::<Vec<_>>());
+
+    // Extending appends in order and reports the new elements
+    let mut list = ChunkList::new(4);
+    list.add(100u64);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    list.subscribe(move |event: &ChunkEvent<u64>| sink.lock().unwrap().push(event.clone()));
+    list.par_extend((0..10u64).into_par_iter());
+    assert_eq!(list.get_list(), [100, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 10);
+    assert_eq!(events[0], ChunkEvent::Inserted { index: 1, value: 0 });
+    assert_eq!(events[9], ChunkEvent::Inserted { index: 10, value: 9 });
+}
+
+#[test]
+fn par_bulk_transforms() {
+    let mut list = ChunkList::new(10);
+    for i in 0..95u32 {
+        list.add(i);
+    }
+
+    let strings: ChunkList<String> = list.map(|x| format!("#{}", x));
+    assert_eq!(strings.get_chunk_size(), 10);
+    assert_eq!(strings.chunk_amount(), list.chunk_amount());
+    assert_eq!(strings.get(42), "#42");
+
+    // Every other chunk keeps its first five elements; chunks with nothing left are dropped
+    let filtered = list.filter(|x| x % 20 < 5);
+    assert_eq!(filtered.get_list(), (0..95).filter(|x| x % 20 < 5).collect::<Vec<_>>());
+    assert_eq!(filtered.chunk_amount(), 5);
+
+    // Ordered combine: concatenation isn't commutative
+    let joined = list.fold(String::new, |s, x| s + &x.to_string() + ",", |a, b| a + &b);
+    assert_eq!(joined, (0..95).map(|x| format!("{},", x)).collect::<String>());
+    assert_eq!(list.reduce(|a, b| a.max(b)), Some(94));
+    assert_eq!(ChunkList::<u32>::new(4).reduce(|a, b| a + b), None);
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    list.subscribe(move |event: &ChunkEvent<u32>| sink.lock().unwrap().push(event.clone()));
+    list.par_for_each_chunk(|chunk_index, chunk| {
+        if chunk_index == 3 {
+            chunk.iter_mut().for_each(|x| *x += 1000);
+        }
+    });
+    assert_eq!(*list.get(30), 1030);
+    assert_eq!(*list.get(40), 40);
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 10);
+    assert_eq!(events[0], ChunkEvent::Updated { index: 30, old: 30, new: 1030 });
+}
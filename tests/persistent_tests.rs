@@ -0,0 +1,55 @@
+use chunklist::{ChunkList, PersistentChunkList};
+
+#[test]
+fn persistent_versions_share_unchanged_chunks() {
+    let mut v0 = PersistentChunkList::new(10);
+    for i in 0..100 {
+        v0 = v0.push(i);
+    }
+    assert_eq!(v0.chunk_amount(), 10);
+
+    // Only the touched chunk is copied
+    let v1 = v0.set(15, -15);
+    assert_eq!(v1.shared_chunks(&v0), 9);
+    assert_eq!(*v0.get(15), 15);
+    assert_eq!(*v1.get(15), -15);
+
+    // Inserting into a full chunk splits it; everything else is still shared
+    let v2 = v1.insert(0, -1);
+    assert_eq!(v2.len(), 101);
+    assert_eq!(v2.chunk_amount(), 11);
+    assert_eq!(v2.shared_chunks(&v1), 9);
+    assert_eq!(*v2.get(0), -1);
+    assert_eq!(*v2.get(16), -15);
+
+    let v3 = v2.remove_at(0);
+    assert_eq!(v3.get_list(), v1.get_list());
+    assert_eq!(v0.get_list(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn persistent_undo_stack() {
+    let mut history = vec![PersistentChunkList::default()];
+    for word in ["a", "b", "c", "d"] {
+        let next = history.last().unwrap().push(word.to_string());
+        history.push(next);
+    }
+    let edited = history.last().unwrap().set(1, "B".to_string()).remove_at(0);
+    history.push(edited);
+    assert_eq!(history.last().unwrap().get_list(), vec!["B", "c", "d"]);
+
+    // Undo back to an earlier version
+    history.pop();
+    assert_eq!(history.last().unwrap().get_list(), vec!["a", "b", "c", "d"]);
+    assert!(history[0].is_empty());
+
+    // Round trip through a mutable ChunkList
+    let mut list = ChunkList::new(2);
+    for x in 0..5 {
+        list.add(x);
+    }
+    let persistent = PersistentChunkList::from(list);
+    assert_eq!(persistent.len(), 5);
+    assert!(persistent.contains(&4));
+    assert_eq!(persistent.to_chunk_list().get_list(), vec![0, 1, 2, 3, 4]);
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "std")]
+use chunklist::{ChunkList, ChunkQueue, PopError, QueueClosed};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn queue_producers_and_consumers_drain_on_close() {
+    let queue = Arc::new(ChunkQueue::new(64));
+
+    let consumers: Vec<_> = (0..3)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                let mut sum = 0u64;
+                while let Some(x) = queue.pop() {
+                    sum += x;
+                }
+                sum
+            })
+        })
+        .collect();
+    // One batch consumer, taking whole chunks
+    let batch_consumer = {
+        let queue = Arc::clone(&queue);
+        thread::spawn(move || {
+            let mut sum = 0u64;
+            while let Some(chunk) = queue.pop_chunk() {
+                assert!(chunk.len() <= 64);
+                sum += chunk.iter().sum::<u64>();
+            }
+            sum
+        })
+    };
+
+    for x in 1..=10_000u64 {
+        queue.push(x).unwrap();
+    }
+    queue.close();
+    assert_eq!(queue.push(0), Err(QueueClosed(0)));
+
+    let mut total: u64 = consumers.into_iter().map(|c| c.join().unwrap()).sum();
+    total += batch_consumer.join().unwrap();
+    assert_eq!(total, 10_000 * 10_001 / 2);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn queue_non_blocking_and_timed_pops() {
+    let queue = ChunkQueue::new(4);
+    assert_eq!(queue.try_pop(), Err(PopError::Empty));
+    assert_eq!(queue.pop_timeout(Duration::from_millis(10)), Err(PopError::Timeout));
+
+    for x in 0..10 {
+        queue.push(x).unwrap();
+    }
+    assert_eq!(queue.chunk_amount(), 3);
+    assert_eq!(queue.try_pop(), Ok(0));
+    assert_eq!(queue.pop_chunk(), Some(vec![1, 2, 3]));
+    assert_eq!(queue.pop_timeout(Duration::from_millis(10)), Ok(4));
+
+    queue.close();
+    assert_eq!(queue.pop_chunk(), Some(vec![5, 6, 7]));
+    assert_eq!(queue.pop(), Some(8));
+    assert_eq!(queue.pop(), Some(9));
+    assert_eq!(queue.pop(), None);
+    assert_eq!(queue.try_pop(), Err(PopError::Closed));
+}
+
+#[test]
+fn queue_from_chunklist_keeps_order() {
+    let mut list = ChunkList::new(3);
+    for x in 0..7 {
+        list.add(x);
+    }
+    let queue = ChunkQueue::from(list);
+    assert_eq!(queue.len(), 7);
+    assert_eq!(queue.pop_chunk(), Some(vec![0, 1, 2]));
+    assert_eq!(queue.pop(), Some(3));
+}
@@ -0,0 +1,179 @@
+#![cfg(feature = "std")]
+use chunklist::{ChunkList, ReplicationFollower, ReplicationLeader};
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn wait_until(mut done: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !done() {
+        assert!(Instant::now() < deadline, "timed out waiting for replication");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn replication_tcp_bootstrap_and_catch_up() {
+    let mut initial = ChunkList::new(8);
+    for i in 0..50u32 {
+        initial.add(i);
+    }
+    let leader = ReplicationLeader::new(initial).with_log_capacity(100);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    leader.serve_tcp(listener);
+
+    // A new follower bootstraps from a snapshot, then receives operations as they happen
+    let follower = ReplicationFollower::<u32, _>::new();
+    let handle = {
+        let follower = follower.clone();
+        thread::spawn(move || follower.run_tcp(addr))
+    };
+    wait_until(|| leader.follower_count() == 1);
+    wait_until(|| follower.is_bootstrapped());
+    for i in 0..20 {
+        leader.add(100 + i);
+    }
+    leader.set(3, 7);
+    leader.remove_at(0);
+    leader.remove_all(&7);
+    leader.sort();
+    wait_until(|| follower.seq() == leader.seq());
+    assert_eq!(follower.snapshot().get_list(), leader.snapshot().get_list());
+
+    // Operations made while the follower is away are replayed when it reconnects
+    leader.disconnect_followers();
+    handle.join().unwrap().unwrap();
+    let seq_before = follower.seq();
+    for i in 0..30 {
+        leader.add(i);
+    }
+    leader.remove_at(5);
+    let handle = {
+        let follower = follower.clone();
+        thread::spawn(move || follower.run_tcp(addr))
+    };
+    wait_until(|| follower.seq() == leader.seq());
+    assert_eq!(follower.seq(), seq_before + 31);
+    let layout = |snapshot: chunklist::ChunkListSnapshot<u32>| -> Vec<Vec<u32>> {
+        snapshot.chunks().map(|chunk| chunk.to_vec()).collect()
+    };
+    assert_eq!(layout(follower.snapshot()), layout(leader.snapshot()));
+
+    // Too far behind for the log: the follower gets a fresh snapshot instead
+    leader.disconnect_followers();
+    handle.join().unwrap().unwrap();
+    for i in 0..150 {
+        leader.add(i);
+    }
+    let handle = {
+        let follower = follower.clone();
+        thread::spawn(move || follower.run_tcp(addr))
+    };
+    wait_until(|| follower.seq() == leader.seq());
+    assert_eq!(layout(follower.snapshot()), layout(leader.snapshot()));
+    leader.disconnect_followers();
+    handle.join().unwrap().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn replication_unix_socket() {
+    use std::os::unix::net::UnixListener;
+
+    let path = std::env::temp_dir().join(format!("chunklist-{}-replication.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let leader = ReplicationLeader::new(ChunkList::<String>::new(4));
+    leader.serve_unix(UnixListener::bind(&path).unwrap());
+
+    let followers: Vec<_> = (0..3).map(|_| ReplicationFollower::<String, _>::new()).collect();
+    let handles: Vec<_> = followers
+        .iter()
+        .map(|follower| {
+            let (follower, path) = (follower.clone(), path.clone());
+            thread::spawn(move || follower.run_unix(path))
+        })
+        .collect();
+    wait_until(|| leader.follower_count() == 3);
+    for word in ["delta", "alpha", "charlie", "bravo", "echo"] {
+        leader.add(word.to_string());
+    }
+    leader.sort();
+    for follower in &followers {
+        wait_until(|| follower.seq() == 6);
+        assert_eq!(follower.snapshot().get_list(), vec!["alpha", "bravo", "charlie", "delta", "echo"]);
+    }
+    leader.disconnect_followers();
+    for handle in handles {
+        handle.join().unwrap().unwrap();
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn replication_drops_stalled_follower() {
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    let leader = ReplicationLeader::new(ChunkList::<String>::new(64)).with_queue_capacity(8);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    leader.serve_tcp(listener);
+
+    // A follower that says hello and then never reads
+    let mut stalled = TcpStream::connect(addr).unwrap();
+    let mut hello = chunklist::replication::HELLO_MAGIC.to_vec();
+    hello.extend_from_slice(&[0; 17]);
+    stalled.write_all(&hello).unwrap();
+    wait_until(|| leader.follower_count() == 1);
+
+    // Once the socket buffers fill up its queue does too, and the leader carries on without it
+    let payload = "x".repeat(1000);
+    for _ in 0..20_000 {
+        leader.add(payload.clone());
+    }
+    assert_eq!(leader.follower_count(), 0);
+
+    // Other followers are still served
+    let follower = ReplicationFollower::<String, _>::new();
+    let handle = {
+        let follower = follower.clone();
+        thread::spawn(move || follower.run_tcp(addr))
+    };
+    wait_until(|| follower.seq() == 20_000);
+    leader.add("done".to_string());
+    wait_until(|| follower.seq() == 20_001);
+    assert_eq!(follower.snapshot().len(), 20_001);
+    leader.disconnect_followers();
+    handle.join().unwrap().unwrap();
+    drop(stalled);
+}
+
+#[test]
+fn replication_restarted_leader_rebootstraps_followers() {
+    // Deterministic mode mustn't make two leaders share an epoch
+    let deterministic = |items: &[u32]| {
+        let mut list = ChunkList::new(4);
+        #[cfg(feature = "rayon")]
+        list.set_execution(chunklist::ExecutionConfig::new().with_deterministic(1));
+        items.iter().for_each(|&i| list.add(i));
+        list
+    };
+    let follower = ReplicationFollower::<u32, _>::new();
+    for (initial, added) in [([0], [1, 2, 3]), ([9], [7, 8, 6])] {
+        let leader = ReplicationLeader::new(deterministic(&initial));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        leader.serve_tcp(listener);
+        let handle = {
+            let follower = follower.clone();
+            thread::spawn(move || follower.run_tcp(addr))
+        };
+        added.iter().for_each(|&i| leader.add(i));
+        wait_until(|| leader.follower_count() == 1 && follower.seq() == 3);
+        wait_until(|| follower.snapshot().get_list() == leader.snapshot().get_list());
+        leader.disconnect_followers();
+        handle.join().unwrap().unwrap();
+    }
+    assert_eq!(follower.snapshot().get_list(), vec![9, 7, 8, 6]);
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "rayon")]
+use chunklist::{ChunkEvent, ChunkList, ExecutionConfig};
+use std::sync::{Arc, Mutex};
+
+fn running_totals(items: &[i64]) -> Vec<i64> {
+    items
+        .iter()
+        .scan(0, |acc, x| {
+            *acc += x;
+            Some(*acc)
+        })
+        .collect()
+}
+
+#[test]
+fn scan_inclusive_and_exclusive() {
+    let mut list = ChunkList::new(16);
+    for i in 0..10_000i64 {
+        list.add(i * 7 - 300);
+    }
+    // Empty a chunk in the middle and the last chunk; emptied chunks stay in place
+    for _ in 0..16 {
+        list.remove_at(8_000);
+        list.remove_at(list.len() - 1);
+    }
+    assert_eq!(list.chunk_amount(), 625);
+    // Uneven chunks
+    for i in (0..2_000).rev().step_by(3) {
+        list.remove_at(i);
+    }
+    let items = list.get_list();
+    let inclusive = running_totals(&items);
+    let mut exclusive = vec![0];
+    exclusive.extend_from_slice(&inclusive[..inclusive.len() - 1]);
+    // Associative but not commutative: keep the last non-negative value seen so far
+    let last = |a: &i64, b: &i64| if *b >= 0 { *b } else { *a };
+    let expected: Vec<i64> = items
+        .iter()
+        .scan(-1, |acc, &x| {
+            *acc = last(acc, &x);
+            Some(*acc)
+        })
+        .collect();
+
+    // The parallel passes (every chunk its own task, on several threads) must agree
+    // with a sequential scan
+    let pool = Arc::new(rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap());
+    let configs = [
+        ExecutionConfig::sequential(),
+        ExecutionConfig::new(),
+        ExecutionConfig::new().with_pool(pool).with_sequential_cutoff(1),
+    ];
+    let mut results = Vec::new();
+    for config in configs {
+        list.set_execution(config);
+        let scanned = list.scan_inclusive(|a, b| a + b);
+        assert_eq!(scanned.get_list(), inclusive);
+        assert_eq!(scanned.chunk_amount(), list.chunk_amount());
+        assert_eq!(list.get_list(), items);
+        assert_eq!(list.scan_exclusive(0, |a, b| a + b).get_list(), exclusive);
+        let scanned_last = list.scan_exclusive(-1, last).get_list();
+        assert_eq!(scanned_last[1..], expected[..expected.len() - 1]);
+
+        let mut in_place = list.snapshot().to_chunk_list();
+        in_place.set_execution(list.get_execution().clone());
+        in_place.scan_inclusive_in_place(|a, b| a + b);
+        assert_eq!(in_place.get_list(), inclusive);
+        results.push((in_place.get_list(), scanned_last));
+    }
+    assert!(results.windows(2).all(|pair| pair[0] == pair[1]));
+    assert!(ChunkList::<i64>::new(4).scan_inclusive(|a, b| a + b).is_empty());
+}
+
+#[test]
+fn scan_in_place_reports_updates() {
+    let mut list = ChunkList::new(3);
+    for i in 1..=7i64 {
+        list.add(i);
+    }
+    let snapshot = list.snapshot();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    list.subscribe(move |event: &ChunkEvent<i64>| sink.lock().unwrap().push(event.clone()));
+
+    list.scan_inclusive_in_place(|a, b| a + b);
+    assert_eq!(list.get_list(), [1, 3, 6, 10, 15, 21, 28]);
+    assert_eq!(snapshot.get_list(), [1, 2, 3, 4, 5, 6, 7]);
+    // The first element is unchanged, so six updates
+    assert_eq!(events.lock().unwrap().len(), 6);
+    assert_eq!(events.lock().unwrap()[0], ChunkEvent::Updated { index: 1, old: 2, new: 3 });
+
+    list.scan_exclusive_in_place(0, |a, b| a + b);
+    assert_eq!(list.get_list(), [0, 1, 4, 10, 20, 35, 56]);
+}
@@ -0,0 +1,35 @@
+use chunklist::ChunkList;
+
+#[test]
+fn search_finds_matches_in_index_order() {
+    let mut list = ChunkList::new(10);
+    for i in 0..10_000u32 {
+        list.add(i % 1_000);
+    }
+    list.remove_at(5);
+
+    // Many chunks match; the result is always the nearest one to the requested end
+    for _ in 0..20 {
+        assert_eq!(list.position(|&x| x >= 500), Some(499));
+        assert_eq!(list.rposition(|&x| x < 10), Some(9_008));
+        assert_eq!(list.find_first(|&x| x % 7 == 6), Some(&6));
+        assert_eq!(list.find_last(|&x| x % 7 == 6), Some(&993));
+    }
+    assert_eq!(list.index_of(&5), Some(1_004));
+    assert_eq!(list.index_of(&1_000), None);
+    assert_eq!(list.find_first(|&x| x > 1_000), None);
+    assert_eq!(list.rposition(|&x| x > 1_000), None);
+}
+
+#[test]
+fn search_counts() {
+    let mut list = ChunkList::new(7);
+    for i in 0..1_000u32 {
+        list.add(i % 10);
+    }
+    list.remove_all(&3);
+    assert_eq!(list.count(&4), 100);
+    assert_eq!(list.count(&3), 0);
+    assert_eq!(list.count_if(|&x| x >= 5), 500);
+    assert_eq!(ChunkList::<u32>::new(4).count_if(|_| true), 0);
+}
@@ -0,0 +1,60 @@
+// Run with "cargo test --features serde"
+#![cfg(feature = "serde")]
+use chunklist::ChunkList;
+use serde::{Deserialize, Serialize};
+
+#[test]
+fn serde_flat_round_trip() {
+    let mut list = ChunkList::new(3);
+    for x in [4, 8, 15, 16, 23, 42] {
+        list.add(x);
+    }
+    let json = serde_json::to_string(&list).unwrap();
+    assert_eq!(json, "[4,8,15,16,23,42]");
+
+    // The flat form doesn't know about chunks, so we get the default chunk size back
+    let restored: ChunkList<i32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.get_list(), list.get_list());
+    assert_eq!(restored.get_chunk_size(), 1000);
+
+    // Larger sequences are packed into full chunks
+    let items: Vec<i32> = (0..2_500).collect();
+    let restored: ChunkList<i32> = serde_json::from_str(&serde_json::to_string(&items).unwrap()).unwrap();
+    assert_eq!(restored.get_list(), items);
+    assert_eq!(restored.chunk_amount(), 3);
+}
+
+#[test]
+fn serde_layout_round_trip() {
+    #[derive(Serialize, Deserialize)]
+    struct Document {
+        name: String,
+        #[serde(with = "chunklist::serde_layout")]
+        lines: ChunkList<String>,
+    }
+
+    let mut lines = ChunkList::new(4);
+    for i in 0..10 {
+        lines.add(format!("line {}", i));
+    }
+    lines.remove_at(1);
+    lines.insert(5, "inserted".to_string());
+    let doc = Document {
+        name: "notes".to_string(),
+        lines,
+    };
+
+    let json = serde_json::to_string(&doc).unwrap();
+    let restored: Document = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.name, "notes");
+    assert_eq!(restored.lines.get_chunk_size(), 4);
+    assert_eq!(restored.lines.chunk_amount(), doc.lines.chunk_amount());
+    let layout = |list: &ChunkList<String>| -> Vec<Vec<String>> {
+        list.snapshot().chunks().map(|chunk| chunk.to_vec()).collect()
+    };
+    assert_eq!(layout(&restored.lines), layout(&doc.lines));
+
+    // Chunks longer than the chunk size are rejected
+    let bad = r#"{"name":"x","lines":{"chunk_size":1,"chunks":[["a","b"]]}}"#;
+    assert!(serde_json::from_str::<Document>(bad).is_err());
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "std")]
+use chunklist::ShardedChunkList;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn sharded_parallel_writers() {
+    let list = Arc::new(ShardedChunkList::new(8, 100));
+
+    // Four threads add and remove in parallel
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            let list = Arc::clone(&list);
+            thread::spawn(move || {
+                let mut rng = StdRng::seed_from_u64(t as u64);
+                for _ in 0..2_500 {
+                    list.add(rng.gen_range(0..1000) * 4 + t);
+                }
+                list.remove_all(&t);
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let total = list.len();
+    assert!(total <= 10_000);
+    for t in 0..4 {
+        assert!(!list.contains(&t));
+    }
+
+    // The merged view is sorted and holds every element
+    let sorted = list.sorted();
+    assert_eq!(sorted.len(), total);
+    let items = sorted.get_list();
+    assert!(items.windows(2).all(|w| w[0] <= w[1]));
+    // Packed into full chunks
+    assert_eq!(sorted.chunk_amount(), total.div_ceil(sorted.get_chunk_size()));
+}
+
+#[test]
+fn sharded_custom_key() {
+    // Route by value range: one shard per hundred
+    let list = ShardedChunkList::with_key(3, 10, |x: &i32| (*x / 100) as u64);
+    for x in [5, 150, 250, 42, 199, 7] {
+        list.add(x);
+    }
+    assert_eq!(list.shard_of(&42), 0);
+    assert_eq!(list.with_shard(0, |shard| shard.len()), 3);
+    assert_eq!(list.with_shard(1, |shard| shard.get_list()), vec![150, 199]);
+
+    list.remove(&150);
+    assert!(!list.contains(&150));
+    assert_eq!(list.sorted().get_list(), vec![5, 7, 42, 199, 250]);
+
+    let shards = list.into_shards();
+    assert_eq!(shards.len(), 3);
+}
@@ -0,0 +1,65 @@
+use chunklist::ChunkList;
+
+#[test]
+fn snapshot_is_isolated_from_writes() {
+    let mut list = ChunkList::new(10);
+    for i in 0..100 {
+        list.add(i);
+    }
+    let snapshot = list.snapshot();
+
+    // Mutate every way we can; the snapshot must keep its point-in-time image
+    list.set(0, 1000);
+    list.remove_all(&50);
+    list.insert(5, -5);
+    list.add(100);
+    list.sort();
+
+    assert_eq!(snapshot.len(), 100);
+    assert_eq!(*snapshot.get(0), 0);
+    assert!(snapshot.contains(&50));
+    assert!(!snapshot.contains(&1000));
+    assert_eq!(snapshot.get_list(), (0..100).collect::<Vec<_>>());
+    assert!(!list.contains(&50));
+
+    // A snapshot can be turned back into a list of its own
+    let restored = snapshot.to_chunk_list();
+    assert_eq!(restored.get_list(), snapshot.get_list());
+    assert_eq!(restored.chunk_amount(), 10);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn snapshot_scan_concurrent_with_writers() {
+    use chunklist::ConcurrentChunkList;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    let list = Arc::new(ConcurrentChunkList::new(16));
+    for _ in 0..1_000 {
+        list.add(1u64);
+    }
+    let snapshot = list.snapshot();
+    let barrier = Arc::new(Barrier::new(2));
+
+    let writer = {
+        let list = Arc::clone(&list);
+        let barrier = Arc::clone(&barrier);
+        thread::spawn(move || {
+            barrier.wait();
+            for i in 0..1_000 {
+                list.set(i, 2);
+            }
+        })
+    };
+
+    // Scan the pinned image while the writer rewrites every element
+    barrier.wait();
+    for _ in 0..20 {
+        assert_eq!(snapshot.iter().sum::<u64>(), 1_000);
+    }
+    writer.join().unwrap();
+
+    assert_eq!(snapshot.iter().sum::<u64>(), 1_000);
+    assert_eq!(list.snapshot().iter().sum::<u64>(), 2_000);
+}
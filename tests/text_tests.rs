@@ -0,0 +1,90 @@
+#![cfg(feature = "std")]
+use chunklist::{ChunkList, TextError};
+use std::io::Cursor;
+
+#[test]
+fn text_lines_round_trip() {
+    let input: String = (0..2_500).map(|i| format!("{}\n", i * 3)).collect();
+    let list = ChunkList::<u64>::from_reader_lines(Cursor::new(&input), 100, |line| line.parse::<u64>()).unwrap();
+    assert_eq!(list.len(), 2_500);
+    assert_eq!(list.chunk_amount(), 25);
+    assert_eq!(*list.get(2_499), 7_497);
+
+    let mut output = Vec::new();
+    list.write_lines(&mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), input);
+
+    // The error carries the line number of the first bad record, even in a later block
+    let bad = "1\n2\r\n3\nfour\n5\nsix\n";
+    let error = ChunkList::<u64>::from_reader_lines(Cursor::new(bad), 2, |line| line.parse::<u64>()).unwrap_err();
+    assert!(matches!(error, TextError::InvalidRecord { line: 4, .. }), "{}", error);
+    assert!(error.to_string().starts_with("line 4: "));
+}
+
+#[test]
+fn text_csv_import_export() {
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    struct Person {
+        name: String,
+        age: u32,
+    }
+    let parse = |fields: &[String]| -> Result<Person, String> {
+        match fields {
+            [name, age] => Ok(Person {
+                name: name.clone(),
+                age: age.parse().map_err(|e| format!("bad age: {}", e))?,
+            }),
+            _ => Err(format!("expected 2 fields, got {}", fields.len())),
+        }
+    };
+
+    let csv = "name,age\nAda,36\n\"Hopper, Grace\",85\n\"Quote \"\"Q\"\" Person\",40\n\"Multi\nLine\",1\n";
+    let people = ChunkList::from_csv(Cursor::new(csv), 2, true, parse).unwrap();
+    assert_eq!(people.len(), 4);
+    assert_eq!(people.get(1).name, "Hopper, Grace");
+    assert_eq!(people.get(2).name, "Quote \"Q\" Person");
+    assert_eq!(people.get(3).name, "Multi\nLine");
+
+    let mut output = Vec::new();
+    people
+        .write_csv(&mut output, Some(&["name", "age"]), |p| vec![p.name.clone(), p.age.to_string()])
+        .unwrap();
+    let reparsed = ChunkList::from_csv(Cursor::new(output), 3, true, parse).unwrap();
+    assert_eq!(reparsed.get_list(), people.get_list());
+
+    // Line numbers count the lines of multi-line records
+    let bad = "\"a\nb\",1\nc,2,3\n";
+    let error = ChunkList::from_csv(Cursor::new(bad), 10, false, parse).unwrap_err();
+    assert!(matches!(error, TextError::InvalidRecord { line: 3, .. }), "{}", error);
+    let error = ChunkList::from_csv(Cursor::new("x,1\n\"open,2\n"), 10, false, parse).unwrap_err();
+    assert!(matches!(error, TextError::InvalidRecord { line: 2, .. }), "{}", error);
+}
+
+#[test]
+fn text_extends_on_the_list_execution_config() {
+    let input: String = (0..5_000).map(|i| format!("{}\n", i)).collect();
+    let mut list = ChunkList::new(64);
+    list.add(-1i64);
+    #[cfg(feature = "rayon")]
+    {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        list.set_execution(chunklist::ExecutionConfig::new().with_pool(std::sync::Arc::new(pool)).with_sequential_cutoff(1));
+    }
+    list.extend_from_reader_lines(Cursor::new(&input), |line| line.parse::<i64>()).unwrap();
+    assert_eq!(list.len(), 5_001);
+    assert_eq!(list.get_list(), (-1..5_000).collect::<Vec<i64>>());
+
+    // A bad line leaves the list as it was
+    let error = list.extend_from_reader_lines(Cursor::new("1\nx\n"), |line| line.parse::<i64>()).unwrap_err();
+    assert!(matches!(error, TextError::InvalidRecord { line: 2, .. }), "{}", error);
+    assert_eq!(list.len(), 5_001);
+
+    // A quoted field spanning many lines is one record
+    let long_field: String = (0..2_000).map(|i| format!("line {}\n", i)).collect();
+    let csv = format!("\"{}\",1\nnext,2\n", long_field);
+    let mut records = ChunkList::new(8);
+    records.extend_from_csv(Cursor::new(csv), false, |fields| Ok::<_, String>(fields.to_vec())).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records.get(0)[0], long_field);
+    assert_eq!(records.get(1)[0], "next");
+}
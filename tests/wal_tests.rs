@@ -0,0 +1,158 @@
+#![cfg(feature = "std")]
+use chunklist::{ChunkList, DurableChunkList};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("chunklist-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn wal_recovers_after_reopen() {
+    let dir = temp_dir("wal-recover");
+    let mut mirror = ChunkList::new(8);
+    {
+        let mut list = DurableChunkList::open(&dir, 8).unwrap();
+        list.set_checkpoint_interval(25);
+        for i in 0..60i32 {
+            list.add(i % 17).unwrap();
+            mirror.add(i % 17);
+        }
+        list.set(3, 100).unwrap();
+        mirror.set(3, 100);
+        assert_eq!(list.remove_at(10).unwrap(), mirror.remove_at(10));
+        list.remove_all(&5).unwrap();
+        mirror.remove_all(&5);
+        list.sort().unwrap();
+        mirror.sort();
+        // 64 records: two checkpoints, then 14 records in the log
+        assert_eq!(list.records_since_checkpoint(), 14);
+        assert_eq!(list.list().get_list(), mirror.get_list());
+        // Dropped without a final checkpoint, like a crash
+    }
+
+    let list = DurableChunkList::<i32, _>::open(&dir, 8).unwrap();
+    assert_eq!(list.list().get_list(), mirror.get_list());
+    assert_eq!(list.list().chunk_amount(), mirror.chunk_amount());
+    assert_eq!(list.records_since_checkpoint(), 14);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn wal_truncates_torn_tail_and_skips_checkpointed_records() {
+    let dir = temp_dir("wal-torn");
+    {
+        let mut list = DurableChunkList::open(&dir, 4).unwrap();
+        for word in ["a", "b", "c"] {
+            list.add(word.to_string()).unwrap();
+        }
+    }
+    let wal = dir.join("wal.log");
+    let good_len = fs::metadata(&wal).unwrap().len();
+
+    // Half a record at the end, as if the process died mid-write
+    OpenOptions::new().append(true).open(&wal).unwrap().write_all(&[20, 0, 0, 0, 1, 2]).unwrap();
+    {
+        let mut list = DurableChunkList::<String, _>::open(&dir, 4).unwrap();
+        assert_eq!(list.list().get_list(), vec!["a", "b", "c"]);
+        assert_eq!(fs::metadata(&wal).unwrap().len(), good_len);
+        list.add("d".to_string()).unwrap();
+    }
+
+    // A crash right after a checkpoint, before the log was emptied
+    let saved_log = fs::read(&wal).unwrap();
+    {
+        let mut list = DurableChunkList::<String, _>::open(&dir, 4).unwrap();
+        list.checkpoint().unwrap();
+        assert_eq!(list.records_since_checkpoint(), 0);
+    }
+    fs::write(&wal, saved_log).unwrap();
+    let list = DurableChunkList::<String, _>::open(&dir, 4).unwrap();
+    assert_eq!(list.list().get_list(), vec!["a", "b", "c", "d"]);
+    assert_eq!(list.records_since_checkpoint(), 0);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn wal_keeps_ops_when_checkpoint_fails_and_rejects_gaps() {
+    let dir = temp_dir("wal-checkpoint");
+    {
+        let mut list = DurableChunkList::open(&dir, 4).unwrap();
+        list.set_checkpoint_interval(2);
+        list.add(1u8).unwrap();
+
+        // A directory in the way of the checkpoint file makes every checkpoint fail
+        fs::create_dir(dir.join("checkpoint.tmp")).unwrap();
+        list.add(2).unwrap();
+        assert!(list.checkpoint_error().is_some());
+        assert!(list.checkpoint().is_err());
+        list.add(3).unwrap();
+        assert_eq!(list.records_since_checkpoint(), 3);
+        assert_eq!(list.list().get_list(), vec![1, 2, 3]);
+
+        // The next op retries the checkpoint
+        fs::remove_dir(dir.join("checkpoint.tmp")).unwrap();
+        list.add(4).unwrap();
+        assert!(list.checkpoint_error().is_none());
+        assert_eq!(list.records_since_checkpoint(), 0);
+        list.set_checkpoint_interval(10);
+        list.add(5).unwrap();
+        list.add(6).unwrap();
+        list.add(7).unwrap();
+    }
+
+    // Drop the record in the middle of the log: replay must not skip over it
+    let wal = dir.join("wal.log");
+    let log = fs::read(&wal).unwrap();
+    let record_len = |at: usize| 8 + u32::from_le_bytes(log[at..at + 4].try_into().unwrap()) as usize;
+    let first = record_len(0);
+    let second = record_len(first);
+    let mut gapped = log[..first].to_vec();
+    gapped.extend_from_slice(&log[first + second..]);
+    fs::write(&wal, gapped).unwrap();
+    assert!(DurableChunkList::<u8, _>::open(&dir, 4).is_err());
+
+    fs::write(&wal, log).unwrap();
+    let list = DurableChunkList::<u8, _>::open(&dir, 4).unwrap();
+    assert_eq!(list.list().get_list(), vec![1, 2, 3, 4, 5, 6, 7]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn wal_refuses_writes_after_a_failed_record() {
+    use chunklist::FormatError;
+    use std::io::ErrorKind;
+
+    let dir = temp_dir("wal-failed");
+    {
+        let mut list = DurableChunkList::open(&dir, 4).unwrap();
+        list.add(1u32).unwrap();
+        list.add(2).unwrap();
+    }
+    let wal = dir.join("wal.log");
+    let saved_log = fs::read(&wal).unwrap();
+
+    // Every write to /dev/full fails, and it can't be truncated either
+    fs::remove_file(&wal).unwrap();
+    std::os::unix::fs::symlink("/dev/full", &wal).unwrap();
+    {
+        let mut list = DurableChunkList::<u32, _>::open(&dir, 4).unwrap();
+        let before = list.list().get_list();
+        assert!(matches!(list.add(3), Err(FormatError::Io(e)) if e.kind() == ErrorKind::StorageFull));
+        assert_eq!(list.list().get_list(), before);
+        // The failed record couldn't be rolled back, so nothing more gets logged
+        assert!(matches!(list.add(4), Err(FormatError::Io(e)) if e.kind() == ErrorKind::Other));
+        assert_eq!(list.list().get_list(), before);
+        assert_eq!(list.records_since_checkpoint(), 0);
+    }
+
+    fs::remove_file(&wal).unwrap();
+    fs::write(&wal, saved_log).unwrap();
+    let list = DurableChunkList::<u32, _>::open(&dir, 4).unwrap();
+    assert_eq!(list.list().get_list(), vec![1, 2]);
+    fs::remove_dir_all(&dir).unwrap();
+}